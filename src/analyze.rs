@@ -0,0 +1,9 @@
+mod anr;
+mod java;
+mod native;
+mod process;
+
+pub use anr::{anrs, Anr, AnrEvent, Anrs, CpuUsage, CpuUsageEntry};
+pub use java::{merge_stack_traces, JavaFrame, JavaStackTrace, MergeStackTraces};
+pub use native::{native_crashes, NativeCrash, NativeCrashes, NativeFrame};
+pub use process::{ProcessChange, ProcessLifetime, ProcessTracker};
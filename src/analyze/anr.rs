@@ -0,0 +1,499 @@
+use crate::message::Message;
+use chrono::NaiveDateTime;
+use std::collections::VecDeque;
+
+// The number of recent `am_anr` events kept for linking to ANR reports.
+const MAX_EVENTS: usize = 16;
+
+/// Finds ANR reports written by `ActivityManager`.
+///
+/// A report starts with an `ANR in <process>` line and continues with the
+/// lines logged by the same `ActivityManager` call. Each report is linked to
+/// the most recent `am_anr` event for the same process ID, if one was seen
+/// before the report ended.
+///
+/// # Examples
+///
+/// ```
+/// use logcat::{analyze, parse};
+///
+/// let source = "...";
+/// let messages = source.lines().filter_map(|line| parse::threadtime(line).ok());
+/// for anr in analyze::anrs(messages) {
+///     println!("ANR in {}: {:?}", anr.process_name(), anr.reason());
+/// }
+/// ```
+pub fn anrs<I>(messages: I) -> Anrs<I::IntoIter>
+where
+    I: IntoIterator<Item = Message>,
+{
+    Anrs {
+        inner: messages.into_iter(),
+        pending: None,
+        events: VecDeque::new(),
+    }
+}
+
+/// An iterator over ANR reports.
+///
+/// This struct is created by [`anrs`].
+pub struct Anrs<I> {
+    inner: I,
+    pending: Option<PendingAnr>,
+    events: VecDeque<AnrEvent>,
+}
+
+struct PendingAnr {
+    first: Message,
+    text: String,
+}
+
+impl PendingAnr {
+    fn continues(&self, msg: &Message) -> bool {
+        msg.tag() == self.first.tag()
+            && msg.process_id() == self.first.process_id()
+            && msg.thread_id() == self.first.thread_id()
+            && msg.date_time() == self.first.date_time()
+    }
+}
+
+impl<I> Anrs<I> {
+    fn finish(&mut self, pending: PendingAnr) -> Option<Anr> {
+        let mut anr = Anr::parse(&pending.text)?;
+        anr.date_time = pending.first.date_time();
+        anr.event = self
+            .events
+            .iter()
+            .rev()
+            .find(|event| Some(event.pid) == anr.pid)
+            .cloned();
+        Some(anr)
+    }
+}
+
+impl<I: Iterator<Item = Message>> Iterator for Anrs<I> {
+    type Item = Anr;
+
+    fn next(&mut self) -> Option<Anr> {
+        loop {
+            let msg = match self.inner.next() {
+                Some(msg) => msg,
+                None => {
+                    let pending = self.pending.take()?;
+                    return self.finish(pending);
+                }
+            };
+
+            if msg.tag() == "am_anr" {
+                if let Some(event) = AnrEvent::from_message(&msg) {
+                    if self.events.len() == MAX_EVENTS {
+                        self.events.pop_front();
+                    }
+                    self.events.push_back(event);
+                }
+                continue;
+            }
+
+            match &mut self.pending {
+                Some(pending) if pending.continues(&msg) => {
+                    pending.text.push('\n');
+                    pending.text.push_str(msg.content());
+                    continue;
+                }
+                _ => {}
+            }
+
+            let finished = self.pending.take();
+            if msg.tag() == "ActivityManager" && msg.content().starts_with("ANR in ") {
+                self.pending = Some(PendingAnr {
+                    text: msg.content().to_owned(),
+                    first: msg,
+                });
+            }
+
+            if let Some(anr) = finished.and_then(|pending| self.finish(pending)) {
+                return Some(anr);
+            }
+        }
+    }
+}
+
+/// An `am_anr` event from the events buffer.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AnrEvent {
+    date_time: Option<NaiveDateTime>,
+    user: i32,
+    pid: i32,
+    package: String,
+    flags: i64,
+    reason: String,
+}
+
+impl AnrEvent {
+    /// Parses an `am_anr` event, such as
+    /// `[0,1234,com.example,952745541,Input dispatching timed out]`.
+    ///
+    /// Returns `None` if `message` is not an `am_anr` event.
+    pub fn from_message(message: &Message) -> Option<AnrEvent> {
+        if message.tag() != "am_anr" {
+            return None;
+        }
+
+        let fields = message
+            .content()
+            .trim()
+            .strip_prefix('[')?
+            .strip_suffix(']')?;
+        let mut fields = fields.splitn(5, ',');
+        Some(AnrEvent {
+            date_time: message.date_time(),
+            user: fields.next()?.parse().ok()?,
+            pid: fields.next()?.parse().ok()?,
+            package: fields.next()?.to_owned(),
+            flags: fields.next()?.parse().ok()?,
+            reason: fields.next()?.to_owned(),
+        })
+    }
+
+    /// Returns the date and time this event was logged.
+    ///
+    /// Returns `None` if the date and time is not available.
+    pub fn date_time(&self) -> Option<NaiveDateTime> {
+        self.date_time
+    }
+
+    /// Returns the user ID of the process.
+    pub fn user(&self) -> i32 {
+        self.user
+    }
+
+    /// Returns the process ID of the process.
+    pub fn process_id(&self) -> i32 {
+        self.pid
+    }
+
+    /// Returns the package name.
+    pub fn package(&self) -> &str {
+        &self.package
+    }
+
+    /// Returns the application info flags of the package.
+    pub fn flags(&self) -> i64 {
+        self.flags
+    }
+
+    /// Returns the reason for the ANR.
+    pub fn reason(&self) -> &str {
+        &self.reason
+    }
+}
+
+/// A row of a [`CpuUsage`] table.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CpuUsageEntry {
+    percent: f32,
+    pid: Option<i32>,
+    name: String,
+    user: Option<f32>,
+    kernel: Option<f32>,
+}
+
+impl CpuUsageEntry {
+    /// Parses a row such as
+    /// `45% 1234/com.example: 40% user + 5% kernel / faults: 100 minor`.
+    fn parse(line: &str) -> Option<CpuUsageEntry> {
+        let (percent, rest) = line.trim().split_once("% ")?;
+        let (name, breakdown) = rest.split_once(": ")?;
+        let (pid, name) = match name.split_once('/') {
+            Some((pid, name)) => (Some(pid.parse().ok()?), name),
+            None => (None, name),
+        };
+
+        let mut entry = CpuUsageEntry {
+            percent: percent.trim_start_matches('+').parse().ok()?,
+            pid,
+            name: name.to_owned(),
+            user: None,
+            kernel: None,
+        };
+
+        let breakdown = breakdown.split(" / ").next().unwrap_or_default();
+        for part in breakdown.split(" + ") {
+            match part.trim().split_once("% ") {
+                Some((value, "user")) => entry.user = value.parse().ok(),
+                Some((value, "kernel")) => entry.kernel = value.parse().ok(),
+                _ => {}
+            }
+        }
+        Some(entry)
+    }
+
+    /// Returns the total CPU usage in percent.
+    pub fn percent(&self) -> f32 {
+        self.percent
+    }
+
+    /// Returns the process ID.
+    ///
+    /// Returns `None` for the `TOTAL` row.
+    pub fn process_id(&self) -> Option<i32> {
+        self.pid
+    }
+
+    /// Returns the process name, or `TOTAL` for the total row.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the CPU usage in user space in percent.
+    ///
+    /// Returns `None` if the value is not available.
+    pub fn user(&self) -> Option<f32> {
+        self.user
+    }
+
+    /// Returns the CPU usage in the kernel in percent.
+    ///
+    /// Returns `None` if the value is not available.
+    pub fn kernel(&self) -> Option<f32> {
+        self.kernel
+    }
+}
+
+/// A CPU usage table of an [`Anr`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct CpuUsage {
+    header: String,
+    entries: Vec<CpuUsageEntry>,
+    total: Option<CpuUsageEntry>,
+}
+
+impl CpuUsage {
+    /// Returns the table header, such as
+    /// `CPU usage from 0ms to 8011ms later`.
+    pub fn header(&self) -> &str {
+        &self.header
+    }
+
+    /// Returns the per-process rows.
+    pub fn entries(&self) -> &[CpuUsageEntry] {
+        &self.entries
+    }
+
+    /// Returns the `TOTAL` row.
+    ///
+    /// Returns `None` if the row is not available.
+    pub fn total(&self) -> Option<&CpuUsageEntry> {
+        self.total.as_ref()
+    }
+}
+
+/// An ANR (Application Not Responding) report.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Anr {
+    date_time: Option<NaiveDateTime>,
+    process_name: String,
+    component: Option<String>,
+    pid: Option<i32>,
+    reason: Option<String>,
+    parent: Option<String>,
+    load: Option<[f32; 3]>,
+    cpu_usage: Vec<CpuUsage>,
+    event: Option<AnrEvent>,
+}
+
+impl Anr {
+    /// Parses the lines of an ANR report.
+    ///
+    /// Returns `None` if `text` does not start with an `ANR in` line.
+    pub fn parse(text: &str) -> Option<Anr> {
+        let mut lines = text.lines();
+
+        // ANR in com.example (com.example/.MainActivity)
+        let process = lines.next()?.strip_prefix("ANR in ")?.trim();
+        let (process_name, component) = match process.split_once(" (") {
+            Some((name, component)) => (name, component.strip_suffix(')')),
+            None => (process, None),
+        };
+
+        let mut anr = Anr {
+            date_time: None,
+            process_name: process_name.to_owned(),
+            component: component.map(str::to_owned),
+            pid: None,
+            reason: None,
+            parent: None,
+            load: None,
+            cpu_usage: Vec::new(),
+            event: None,
+        };
+
+        for line in lines {
+            if let Some(pid) = line.strip_prefix("PID: ") {
+                anr.pid = pid.trim().parse().ok();
+            } else if let Some(reason) = line.strip_prefix("Reason: ") {
+                anr.reason = Some(reason.to_owned());
+            } else if let Some(parent) = line.strip_prefix("Parent: ") {
+                anr.parent = Some(parent.to_owned());
+            } else if let Some(load) = line.strip_prefix("Load: ") {
+                let load: Vec<f32> = load
+                    .split('/')
+                    .filter_map(|value| value.trim().parse().ok())
+                    .collect();
+                anr.load = load.try_into().ok();
+            } else if line.starts_with("CPU usage ") {
+                let header = line.split(" (").next().unwrap_or(line);
+                anr.cpu_usage.push(CpuUsage {
+                    header: header.trim_end_matches(':').to_owned(),
+                    entries: Vec::new(),
+                    total: None,
+                });
+            } else if let Some(table) = anr.cpu_usage.last_mut() {
+                // Rows of threads are indented below their process.
+                if line.starts_with("    ") {
+                    continue;
+                }
+                if let Some(entry) = CpuUsageEntry::parse(line) {
+                    if entry.pid.is_none() && entry.name == "TOTAL" {
+                        table.total = Some(entry);
+                    } else {
+                        table.entries.push(entry);
+                    }
+                }
+            }
+        }
+
+        Some(anr)
+    }
+
+    /// Returns the date and time the report was logged.
+    ///
+    /// Returns `None` if the report was not read from messages.
+    pub fn date_time(&self) -> Option<NaiveDateTime> {
+        self.date_time
+    }
+
+    /// Returns the name of the process that is not responding.
+    pub fn process_name(&self) -> &str {
+        &self.process_name
+    }
+
+    /// Returns the component that is not responding, such as
+    /// `com.example/.MainActivity`.
+    ///
+    /// Returns `None` if the component is not available.
+    pub fn component(&self) -> Option<&str> {
+        self.component.as_deref()
+    }
+
+    /// Returns the process ID of the process that is not responding.
+    ///
+    /// Returns `None` if the process ID is not available.
+    pub fn process_id(&self) -> Option<i32> {
+        self.pid
+    }
+
+    /// Returns the reason for the ANR.
+    ///
+    /// Returns `None` if the reason is not available.
+    pub fn reason(&self) -> Option<&str> {
+        self.reason.as_deref()
+    }
+
+    /// Returns the parent component.
+    ///
+    /// Returns `None` if the parent is not available.
+    pub fn parent(&self) -> Option<&str> {
+        self.parent.as_deref()
+    }
+
+    /// Returns the 1, 5 and 15 minute load averages.
+    ///
+    /// Returns `None` if the load averages are not available.
+    pub fn load(&self) -> Option<[f32; 3]> {
+        self.load
+    }
+
+    /// Returns the CPU usage tables.
+    pub fn cpu_usage(&self) -> &[CpuUsage] {
+        &self.cpu_usage
+    }
+
+    /// Returns the `am_anr` event logged for this ANR.
+    ///
+    /// Returns `None` if no matching event was seen.
+    pub fn event(&self) -> Option<&AnrEvent> {
+        self.event.as_ref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{analyze, analyze::Anr, parse};
+
+    const ANR: &str = "\
+01-02 10:00:00.000   567   600 I am_anr  : [0,1234,com.example,952745541,Input dispatching timed out]
+01-02 10:00:05.000   567   600 E ActivityManager: ANR in com.example (com.example/.MainActivity)
+01-02 10:00:05.000   567   600 E ActivityManager: PID: 1234
+01-02 10:00:05.000   567   600 E ActivityManager: Reason: Input dispatching timed out
+01-02 10:00:05.000   567   600 E ActivityManager: Load: 5.14 / 4.86 / 4.68
+01-02 10:00:05.000   567   600 E ActivityManager: CPU usage from 0ms to 8011ms later (2021-01-02 10:00:00.000 to 2021-01-02 10:00:08.011):
+01-02 10:00:05.000   567   600 E ActivityManager:   45% 1234/com.example: 40% user + 5% kernel / faults: 100 minor
+01-02 10:00:05.000   567   600 E ActivityManager:     25% 1250/RenderThread: 20% user + 5% kernel
+01-02 10:00:05.000   567   600 E ActivityManager:   +0% 5678/com.new: 0% user + 0% kernel
+01-02 10:00:05.000   567   600 E ActivityManager: 12% TOTAL: 6.5% user + 5% kernel + 0.1% iowait
+01-02 10:00:06.000   567   600 I ActivityManager: Killing 1234:com.example/u0a12 (adj 0): bg anr
+";
+
+    #[test]
+    fn anrs() {
+        let messages = ANR.lines().map(|line| parse::threadtime(line).unwrap());
+        let anrs: Vec<_> = analyze::anrs(messages).collect();
+        assert_eq!(anrs.len(), 1);
+
+        let anr = &anrs[0];
+        assert!(anr.date_time().is_some());
+        assert_eq!(anr.process_name(), "com.example");
+        assert_eq!(anr.component(), Some("com.example/.MainActivity"));
+        assert_eq!(anr.process_id(), Some(1234));
+        assert_eq!(anr.reason(), Some("Input dispatching timed out"));
+        assert_eq!(anr.parent(), None);
+        assert_eq!(anr.load(), Some([5.14, 4.86, 4.68]));
+
+        let tables = anr.cpu_usage();
+        assert_eq!(tables.len(), 1);
+        assert_eq!(tables[0].header(), "CPU usage from 0ms to 8011ms later");
+
+        let entries = tables[0].entries();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].percent(), 45.0);
+        assert_eq!(entries[0].process_id(), Some(1234));
+        assert_eq!(entries[0].name(), "com.example");
+        assert_eq!(entries[0].user(), Some(40.0));
+        assert_eq!(entries[0].kernel(), Some(5.0));
+        assert_eq!(entries[1].name(), "com.new");
+
+        let total = tables[0].total().unwrap();
+        assert_eq!(total.percent(), 12.0);
+        assert_eq!(total.process_id(), None);
+        assert_eq!(total.user(), Some(6.5));
+
+        let event = anr.event().unwrap();
+        assert_eq!(event.user(), 0);
+        assert_eq!(event.process_id(), 1234);
+        assert_eq!(event.package(), "com.example");
+        assert_eq!(event.flags(), 952745541);
+        assert_eq!(event.reason(), "Input dispatching timed out");
+    }
+
+    #[test]
+    fn anr_without_event() {
+        let anr = Anr::parse("ANR in system\nPID: 1").unwrap();
+        assert_eq!(anr.process_name(), "system");
+        assert_eq!(anr.component(), None);
+        assert_eq!(anr.process_id(), Some(1));
+        assert!(anr.event().is_none());
+
+        assert!(Anr::parse("Killing 1234:com.example/u0a12").is_none());
+    }
+}
@@ -0,0 +1,499 @@
+use crate::message::Message;
+
+/// Merges consecutive lines of a Java stack trace into a single message.
+///
+/// Lines are merged when they come from the same process, thread, tag and
+/// level, and look like a continuation of a stack trace (`at ...`,
+/// `Caused by: ...`, `Suppressed: ...` or `... N more`). The content of a
+/// merged message contains the original lines separated by `\n`.
+///
+/// # Examples
+///
+/// ```
+/// use logcat::{analyze, analyze::JavaStackTrace, parse};
+///
+/// let source = "...";
+/// let messages = source.lines().filter_map(|line| parse::threadtime(line).ok());
+/// for msg in analyze::merge_stack_traces(messages) {
+///     if let Some(trace) = JavaStackTrace::from_message(&msg) {
+///         println!("exception = {}", trace.exception_class());
+///     }
+/// }
+/// ```
+pub fn merge_stack_traces<I>(messages: I) -> MergeStackTraces<I::IntoIter>
+where
+    I: IntoIterator<Item = Message>,
+{
+    MergeStackTraces {
+        inner: messages.into_iter(),
+        pending: None,
+        expect_exception: false,
+    }
+}
+
+/// An iterator that merges Java stack traces into single messages.
+///
+/// This struct is created by [`merge_stack_traces`].
+pub struct MergeStackTraces<I> {
+    inner: I,
+    pending: Option<Message>,
+    // The pending message is a `FATAL EXCEPTION` header that has not yet
+    // been followed by the exception line.
+    expect_exception: bool,
+}
+
+impl<I> MergeStackTraces<I> {
+    fn start(&mut self, msg: Message) {
+        self.expect_exception = msg.content().starts_with("FATAL EXCEPTION:");
+        self.pending = Some(msg);
+    }
+
+    fn continues(&mut self, pending: &Message, msg: &Message) -> bool {
+        if pending.process_id() != msg.process_id()
+            || pending.thread_id() != msg.thread_id()
+            || pending.level() != msg.level()
+            || pending.tag() != msg.tag()
+        {
+            return false;
+        }
+
+        if self.expect_exception {
+            if !msg.content().starts_with("Process:") {
+                self.expect_exception = false;
+            }
+            return true;
+        }
+        is_continuation(msg.content())
+    }
+}
+
+impl<I: Iterator<Item = Message>> Iterator for MergeStackTraces<I> {
+    type Item = Message;
+
+    fn next(&mut self) -> Option<Message> {
+        loop {
+            let msg = match self.inner.next() {
+                Some(msg) => msg,
+                None => return self.pending.take(),
+            };
+
+            match self.pending.take() {
+                Some(mut pending) => {
+                    if self.continues(&pending, &msg) {
+                        pending.push_line(msg.content());
+                        self.pending = Some(pending);
+                    } else {
+                        self.start(msg);
+                        return Some(pending);
+                    }
+                }
+                None => self.start(msg),
+            }
+        }
+    }
+}
+
+fn is_continuation(line: &str) -> bool {
+    let line = line.trim_start();
+    is_frame(line)
+        || line.starts_with("Caused by: ")
+        || line.starts_with("Suppressed: ")
+        || is_more(line).is_some()
+}
+
+/// Returns `true` for `at <class>.<method>(...)`, but not for prose such as
+/// `at least 3 retries`.
+fn is_frame(line: &str) -> bool {
+    let Some((method, location)) = line
+        .strip_prefix("at ")
+        .and_then(|frame| frame.split_once('('))
+    else {
+        return false;
+    };
+    method.contains('.') && !method.contains(char::is_whitespace) && location.ends_with(')')
+}
+
+/// Parses `... N more`, returning `N`.
+fn is_more(line: &str) -> Option<usize> {
+    line.strip_prefix("... ")?
+        .strip_suffix(" more")?
+        .parse()
+        .ok()
+}
+
+/// A frame of a [`JavaStackTrace`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct JavaFrame {
+    method: String,
+    file: Option<String>,
+    line: Option<u32>,
+    native: bool,
+}
+
+impl JavaFrame {
+    /// Parses a frame such as `at com.example.Foo.bar(Foo.java:12)`.
+    fn parse(line: &str) -> Option<JavaFrame> {
+        let frame = line.trim_start().strip_prefix("at ")?;
+        let (method, location) = match frame.split_once('(') {
+            Some((method, location)) => (method, location.strip_suffix(')')?),
+            None => (frame, ""),
+        };
+
+        let mut file = None;
+        let mut line = None;
+        match location {
+            "Native Method" => {
+                return Some(JavaFrame {
+                    method: method.to_owned(),
+                    file: None,
+                    line: None,
+                    native: true,
+                })
+            }
+            "" | "Unknown Source" => {}
+            location => match location.rsplit_once(':') {
+                Some((name, number)) => {
+                    if name != "Unknown Source" {
+                        file = Some(name.to_owned());
+                    }
+                    line = number.parse().ok();
+                }
+                None => file = Some(location.to_owned()),
+            },
+        }
+
+        Some(JavaFrame {
+            method: method.to_owned(),
+            file,
+            line,
+            native: false,
+        })
+    }
+
+    /// Returns the fully qualified method name, such as `com.example.Foo.bar`.
+    pub fn method(&self) -> &str {
+        &self.method
+    }
+
+    /// Returns the source file name.
+    ///
+    /// Returns `None` if the source file is unknown.
+    pub fn file(&self) -> Option<&str> {
+        self.file.as_deref()
+    }
+
+    /// Returns the source line number.
+    ///
+    /// Returns `None` if the line number is unknown.
+    pub fn line(&self) -> Option<u32> {
+        self.line
+    }
+
+    /// Returns `true` if the frame is a native method.
+    pub fn is_native(&self) -> bool {
+        self.native
+    }
+}
+
+/// A Java exception and its stack trace.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct JavaStackTrace {
+    thread: Option<String>,
+    process: Option<String>,
+    exception_class: String,
+    message: Option<String>,
+    frames: Vec<JavaFrame>,
+    frames_omitted: usize,
+    suppressed: Vec<JavaStackTrace>,
+    cause: Option<Box<JavaStackTrace>>,
+}
+
+impl JavaStackTrace {
+    /// Parses the stack trace in the content of `message`.
+    ///
+    /// The message is usually produced by [`merge_stack_traces`].
+    ///
+    /// Returns `None` if the content is not a stack trace.
+    pub fn from_message(message: &Message) -> Option<JavaStackTrace> {
+        JavaStackTrace::parse(message.content())
+    }
+
+    /// Parses a stack trace from lines of text.
+    ///
+    /// Returns `None` if `text` is not a stack trace.
+    pub fn parse(text: &str) -> Option<JavaStackTrace> {
+        let mut lines = text.lines().peekable();
+
+        // FATAL EXCEPTION: main
+        // Process: com.example, PID: 1234
+        let mut thread = None;
+        let mut process = None;
+        while let Some(line) = lines.peek() {
+            if let Some(name) = line.strip_prefix("FATAL EXCEPTION:") {
+                thread = Some(name.trim().to_owned());
+            } else if let Some(rest) = line.strip_prefix("Process:") {
+                let name = rest.split(',').next().unwrap_or_default();
+                process = Some(name.trim().to_owned());
+            } else {
+                break;
+            }
+            lines.next();
+        }
+
+        let header = lines.next()?;
+        let mut trace = JavaStackTrace::parse_throwable(header, 0, &mut lines)?;
+        trace.thread = thread;
+        trace.process = process;
+        Some(trace)
+    }
+
+    fn parse_throwable<'a, I>(
+        header: &str,
+        depth: usize,
+        lines: &mut std::iter::Peekable<I>,
+    ) -> Option<JavaStackTrace>
+    where
+        I: Iterator<Item = &'a str>,
+    {
+        let header = header.trim_start();
+        let (exception_class, message) = match header.split_once(':') {
+            Some((class, message)) => (class, Some(message.trim_start().to_owned())),
+            None => (header.trim_end(), None),
+        };
+        let is_class = exception_class.contains('.')
+            && exception_class
+                .chars()
+                .all(|c| c.is_alphanumeric() || c == '.' || c == '_' || c == '$');
+        if !is_class {
+            return None;
+        }
+
+        let mut trace = JavaStackTrace {
+            thread: None,
+            process: None,
+            exception_class: exception_class.to_owned(),
+            message,
+            frames: Vec::new(),
+            frames_omitted: 0,
+            suppressed: Vec::new(),
+            cause: None,
+        };
+
+        while let Some(&line) = lines.peek() {
+            let line_depth = line.chars().take_while(|&c| c == '\t').count();
+            let body = line.trim_start();
+
+            if let Some(frame) = JavaFrame::parse(body).filter(|_| line_depth > depth) {
+                trace.frames.push(frame);
+            } else if let Some(count) = is_more(body).filter(|_| line_depth > depth) {
+                trace.frames_omitted = count;
+            } else if let Some(header) = body.strip_prefix("Suppressed: ") {
+                if line_depth <= depth {
+                    break;
+                }
+                lines.next();
+                // Keep what was parsed so far if the header is malformed.
+                match JavaStackTrace::parse_throwable(header, line_depth, lines) {
+                    Some(suppressed) => trace.suppressed.push(suppressed),
+                    None => break,
+                }
+                continue;
+            } else if let Some(header) = body.strip_prefix("Caused by: ") {
+                if line_depth != depth {
+                    break;
+                }
+                lines.next();
+                trace.cause = JavaStackTrace::parse_throwable(header, depth, lines).map(Box::new);
+                break;
+            } else {
+                break;
+            }
+            lines.next();
+        }
+
+        Some(trace)
+    }
+
+    /// Returns the name of the thread that crashed, from a
+    /// `FATAL EXCEPTION: <thread>` header.
+    ///
+    /// Returns `None` if the header is not available.
+    pub fn thread_name(&self) -> Option<&str> {
+        self.thread.as_deref()
+    }
+
+    /// Returns the name of the process that crashed, from a
+    /// `Process: <name>, PID: <pid>` header.
+    ///
+    /// Returns `None` if the header is not available.
+    pub fn process_name(&self) -> Option<&str> {
+        self.process.as_deref()
+    }
+
+    /// Returns the fully qualified exception class name, such as
+    /// `java.lang.NullPointerException`.
+    pub fn exception_class(&self) -> &str {
+        &self.exception_class
+    }
+
+    /// Returns the exception message.
+    ///
+    /// Returns `None` if the exception has no message.
+    pub fn message(&self) -> Option<&str> {
+        self.message.as_deref()
+    }
+
+    /// Returns the stack frames, innermost first.
+    pub fn frames(&self) -> &[JavaFrame] {
+        &self.frames
+    }
+
+    /// Returns the number of frames omitted as `... N more` because they
+    /// are in common with the enclosing trace.
+    pub fn frames_omitted(&self) -> usize {
+        self.frames_omitted
+    }
+
+    /// Returns the exceptions suppressed by this exception.
+    pub fn suppressed(&self) -> &[JavaStackTrace] {
+        &self.suppressed
+    }
+
+    /// Returns the exception that caused this exception.
+    ///
+    /// Returns `None` if there is no cause.
+    pub fn cause(&self) -> Option<&JavaStackTrace> {
+        self.cause.as_deref()
+    }
+
+    /// Returns an iterator over the chain of causes, starting with the
+    /// direct cause of this exception.
+    pub fn causes(&self) -> impl Iterator<Item = &JavaStackTrace> {
+        std::iter::successors(self.cause(), |trace| trace.cause())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        analyze::{self, JavaStackTrace},
+        parse,
+    };
+
+    const CRASH: &str = "\
+01-02 10:00:00.000  1234  1234 D MainActivity: onCreate
+01-02 10:00:00.100  1234  1234 E AndroidRuntime: FATAL EXCEPTION: main
+01-02 10:00:00.100  1234  1234 E AndroidRuntime: Process: com.example, PID: 1234
+01-02 10:00:00.100  1234  1234 E AndroidRuntime: java.lang.RuntimeException: Unable to start activity
+01-02 10:00:00.100  1234  1234 E AndroidRuntime: \tat android.app.ActivityThread.performLaunchActivity(ActivityThread.java:3449)
+01-02 10:00:00.100  1234  1234 E AndroidRuntime: \tat java.lang.reflect.Method.invoke(Native Method)
+01-02 10:00:00.100  1234  1234 E AndroidRuntime: \tSuppressed: java.io.IOException: close failed
+01-02 10:00:00.100  1234  1234 E AndroidRuntime: \t\tat com.example.Stream.close(Unknown Source:4)
+01-02 10:00:00.100  1234  1234 E AndroidRuntime: Caused by: java.lang.NullPointerException
+01-02 10:00:00.100  1234  1234 E AndroidRuntime: \tat com.example.MainActivity.onCreate(MainActivity.java:20)
+01-02 10:00:00.100  1234  1234 E AndroidRuntime: \t... 11 more
+01-02 10:00:00.200  1234  1234 I Process: Sending signal. PID: 1234 SIG: 9
+";
+
+    #[test]
+    fn merge() {
+        let messages = CRASH.lines().map(|line| parse::threadtime(line).unwrap());
+        let merged: Vec<_> = analyze::merge_stack_traces(messages).collect();
+
+        assert_eq!(merged.len(), 3);
+        assert_eq!(merged[0].tag(), "MainActivity");
+        assert_eq!(merged[1].tag(), "AndroidRuntime");
+        assert_eq!(merged[1].content().lines().count(), 10);
+        assert_eq!(merged[2].tag(), "Process");
+    }
+
+    #[test]
+    fn merge_different_threads() {
+        let data = "\
+01-02 10:00:00.000  1 1 W System.err: java.io.IOException: a
+01-02 10:00:00.000  1 2 W System.err: \tat com.example.A.a(A.java:1)
+";
+        let messages = data.lines().map(|line| parse::threadtime(line).unwrap());
+        assert_eq!(analyze::merge_stack_traces(messages).count(), 2);
+    }
+
+    #[test]
+    fn merge_prose() {
+        let data = "\
+01-02 10:00:00.000  1 1 W Sync: java.io.IOException: timeout
+01-02 10:00:00.000  1 1 W Sync: \tat com.example.A.a(A.java:1)
+01-02 10:00:00.000  1 1 W Sync: at least 3 retries left
+";
+        let messages = data.lines().map(|line| parse::threadtime(line).unwrap());
+        let merged: Vec<_> = analyze::merge_stack_traces(messages).collect();
+        assert_eq!(merged.len(), 2);
+        assert_eq!(merged[1].content(), "at least 3 retries left");
+    }
+
+    #[test]
+    fn stack_trace_malformed_cause() {
+        let trace = JavaStackTrace::parse(
+            "java.lang.RuntimeException: a\n\tat com.example.A.a(A.java:1)\n\
+             \tSuppressed: not a throwable\n\
+             Caused by: not a throwable either\n\tat com.example.B.b(B.java:2)",
+        )
+        .unwrap();
+        assert_eq!(trace.frames().len(), 1);
+        assert!(trace.suppressed().is_empty());
+        assert!(trace.cause().is_none());
+
+        let trace = JavaStackTrace::parse(
+            "java.lang.RuntimeException: a\n\tat com.example.A.a(A.java:1)\n\
+             Caused by: not a throwable\n\tat com.example.B.b(B.java:2)",
+        )
+        .unwrap();
+        assert_eq!(trace.frames().len(), 1);
+        assert!(trace.cause().is_none());
+    }
+
+    #[test]
+    fn stack_trace() {
+        let messages = CRASH.lines().map(|line| parse::threadtime(line).unwrap());
+        let merged: Vec<_> = analyze::merge_stack_traces(messages).collect();
+
+        assert!(JavaStackTrace::from_message(&merged[0]).is_none());
+
+        let trace = JavaStackTrace::from_message(&merged[1]).unwrap();
+        assert_eq!(trace.thread_name(), Some("main"));
+        assert_eq!(trace.process_name(), Some("com.example"));
+        assert_eq!(trace.exception_class(), "java.lang.RuntimeException");
+        assert_eq!(trace.message(), Some("Unable to start activity"));
+
+        let frames = trace.frames();
+        assert_eq!(frames.len(), 2);
+        assert_eq!(
+            frames[0].method(),
+            "android.app.ActivityThread.performLaunchActivity"
+        );
+        assert_eq!(frames[0].file(), Some("ActivityThread.java"));
+        assert_eq!(frames[0].line(), Some(3449));
+        assert!(frames[1].is_native());
+
+        let suppressed = trace.suppressed();
+        assert_eq!(suppressed.len(), 1);
+        assert_eq!(suppressed[0].exception_class(), "java.io.IOException");
+        assert_eq!(suppressed[0].frames()[0].file(), None);
+        assert_eq!(suppressed[0].frames()[0].line(), Some(4));
+
+        let cause = trace.cause().unwrap();
+        assert_eq!(cause.exception_class(), "java.lang.NullPointerException");
+        assert_eq!(cause.message(), None);
+        assert_eq!(cause.frames().len(), 1);
+        assert_eq!(cause.frames_omitted(), 11);
+        assert_eq!(trace.causes().count(), 1);
+    }
+
+    #[test]
+    fn not_stack_trace() {
+        let cases = ["", "hello world", "Hello: world", "FATAL EXCEPTION: main"];
+
+        for case in &cases {
+            assert!(JavaStackTrace::parse(case).is_none());
+        }
+    }
+}
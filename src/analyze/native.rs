@@ -0,0 +1,599 @@
+use crate::message::Message;
+use chrono::NaiveDateTime;
+
+/// Finds native crash reports written by `crash_dump` with the `DEBUG` tag.
+///
+/// A report starts with a line of asterisks and ends when another report
+/// starts, when a `DEBUG` message from another process is seen, or when
+/// `messages` ends. Messages with other tags are skipped.
+///
+/// # Examples
+///
+/// ```
+/// use logcat::{analyze, parse};
+///
+/// let source = "...";
+/// let messages = source.lines().filter_map(|line| parse::threadtime(line).ok());
+/// for crash in analyze::native_crashes(messages) {
+///     println!("{:?} in {:?}", crash.signal_name(), crash.process_name());
+/// }
+/// ```
+pub fn native_crashes<I>(messages: I) -> NativeCrashes<I::IntoIter>
+where
+    I: IntoIterator<Item = Message>,
+{
+    NativeCrashes {
+        inner: messages.into_iter(),
+        pending: None,
+    }
+}
+
+/// An iterator over native crash reports.
+///
+/// This struct is created by [`native_crashes`].
+pub struct NativeCrashes<I> {
+    inner: I,
+    pending: Option<PendingCrash>,
+}
+
+struct PendingCrash {
+    pid: Option<i32>,
+    date_time: Option<NaiveDateTime>,
+    text: String,
+}
+
+impl PendingCrash {
+    fn finish(self) -> Option<NativeCrash> {
+        let mut crash = NativeCrash::parse(&self.text)?;
+        crash.date_time = self.date_time;
+        Some(crash)
+    }
+}
+
+impl<I: Iterator<Item = Message>> Iterator for NativeCrashes<I> {
+    type Item = NativeCrash;
+
+    fn next(&mut self) -> Option<NativeCrash> {
+        loop {
+            let msg = match self.inner.next() {
+                Some(msg) => msg,
+                None => return self.pending.take().and_then(PendingCrash::finish),
+            };
+            if msg.tag() != "DEBUG" {
+                continue;
+            }
+
+            let finished = if is_header(msg.content()) {
+                self.pending.replace(PendingCrash {
+                    pid: msg.process_id(),
+                    date_time: msg.date_time(),
+                    text: String::new(),
+                })
+            } else {
+                match &mut self.pending {
+                    Some(pending) if pending.pid == msg.process_id() => {
+                        pending.text.push_str(msg.content());
+                        pending.text.push('\n');
+                        None
+                    }
+                    _ => self.pending.take(),
+                }
+            };
+
+            if let Some(crash) = finished.and_then(PendingCrash::finish) {
+                return Some(crash);
+            }
+        }
+    }
+}
+
+fn is_header(line: &str) -> bool {
+    line.trim().starts_with("*** *** ***")
+}
+
+/// A frame of a [`NativeCrash`] backtrace.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NativeFrame {
+    index: u32,
+    pc: u64,
+    library: String,
+    library_offset: Option<u64>,
+    symbol: Option<String>,
+    symbol_offset: Option<u64>,
+    build_id: Option<String>,
+}
+
+impl NativeFrame {
+    /// Parses a frame such as
+    /// `#00 pc 000000000008a900  /system/lib64/libc.so (abort+160) (BuildId: 58122560)`.
+    fn parse(line: &str) -> Option<NativeFrame> {
+        let rest = line.trim_start().strip_prefix('#')?;
+        let (index, rest) = rest.split_once(char::is_whitespace)?;
+        let rest = rest.trim_start().strip_prefix("pc")?.trim_start();
+        let (pc, mut rest) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+
+        let mut frame = NativeFrame {
+            index: index.parse().ok()?,
+            pc: u64::from_str_radix(pc, 16).ok()?,
+            library: String::new(),
+            library_offset: None,
+            symbol: None,
+            symbol_offset: None,
+            build_id: None,
+        };
+
+        // Annotations are parenthesized groups after the library name, and
+        // symbols may contain parentheses themselves, so match from the end.
+        rest = rest.trim();
+        while let Some((head, group)) = split_last_group(rest) {
+            if let Some(build_id) = group.strip_prefix("BuildId: ") {
+                frame.build_id = Some(build_id.to_owned());
+            } else if let Some(offset) = group.strip_prefix("offset 0x") {
+                frame.library_offset = u64::from_str_radix(offset, 16).ok();
+            } else if group != "deleted" {
+                match group.rsplit_once('+') {
+                    Some((symbol, offset)) if offset.parse::<u64>().is_ok() => {
+                        frame.symbol = Some(symbol.to_owned());
+                        frame.symbol_offset = offset.parse().ok();
+                    }
+                    _ => frame.symbol = Some(group.to_owned()),
+                }
+            }
+            rest = head.trim_end();
+        }
+        frame.library = rest.to_owned();
+
+        Some(frame)
+    }
+
+    /// Returns the position of this frame in the backtrace, starting at 0.
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+
+    /// Returns the program counter, relative to the start of the library.
+    pub fn pc(&self) -> u64 {
+        self.pc
+    }
+
+    /// Returns the path of the library or mapping containing the frame.
+    pub fn library(&self) -> &str {
+        &self.library
+    }
+
+    /// Returns the offset of the library within its file, such as for a
+    /// library loaded directly from an APK.
+    ///
+    /// Returns `None` if the offset is not available.
+    pub fn library_offset(&self) -> Option<u64> {
+        self.library_offset
+    }
+
+    /// Returns the symbol containing the frame.
+    ///
+    /// Returns `None` if the symbol is not available.
+    pub fn symbol(&self) -> Option<&str> {
+        self.symbol.as_deref()
+    }
+
+    /// Returns the offset of the frame from the start of the symbol.
+    ///
+    /// Returns `None` if the offset is not available.
+    pub fn symbol_offset(&self) -> Option<u64> {
+        self.symbol_offset
+    }
+
+    /// Returns the build ID of the library.
+    ///
+    /// Returns `None` if the build ID is not available.
+    pub fn build_id(&self) -> Option<&str> {
+        self.build_id.as_deref()
+    }
+}
+
+/// Splits `text` ending in a parenthesized group into the text before the
+/// group and the contents of the group.
+fn split_last_group(text: &str) -> Option<(&str, &str)> {
+    let inner = text.strip_suffix(')')?;
+    let mut depth = 0;
+    for (i, c) in inner.char_indices().rev() {
+        match c {
+            ')' => depth += 1,
+            '(' if depth == 0 => return Some((&text[..i], &inner[i + 1..])),
+            '(' => depth -= 1,
+            _ => {}
+        }
+    }
+    None
+}
+
+/// A native crash report.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NativeCrash {
+    date_time: Option<NaiveDateTime>,
+    fingerprint: Option<String>,
+    abi: Option<String>,
+    pid: Option<i32>,
+    tid: Option<i32>,
+    thread_name: Option<String>,
+    process_name: Option<String>,
+    signal: i32,
+    signal_name: Option<String>,
+    code: Option<i32>,
+    code_name: Option<String>,
+    fault_address: Option<u64>,
+    abort_message: Option<String>,
+    cause: Option<String>,
+    registers: Vec<(String, u64)>,
+    frames: Vec<NativeFrame>,
+}
+
+#[derive(PartialEq)]
+enum Section {
+    Header,
+    Backtrace,
+    Other,
+}
+
+impl NativeCrash {
+    /// Parses the lines of a native crash report.
+    ///
+    /// Returns `None` if `text` does not contain a `signal` line.
+    pub fn parse(text: &str) -> Option<NativeCrash> {
+        let mut crash = NativeCrash {
+            date_time: None,
+            fingerprint: None,
+            abi: None,
+            pid: None,
+            tid: None,
+            thread_name: None,
+            process_name: None,
+            signal: 0,
+            signal_name: None,
+            code: None,
+            code_name: None,
+            fault_address: None,
+            abort_message: None,
+            cause: None,
+            registers: Vec::new(),
+            frames: Vec::new(),
+        };
+        let mut has_signal = false;
+        let mut section = Section::Header;
+
+        for line in text.lines() {
+            let line = line.trim();
+            if line.is_empty() || is_header(line) {
+                continue;
+            }
+
+            if line == "backtrace:" {
+                section = Section::Backtrace;
+                continue;
+            }
+
+            match section {
+                Section::Header => {
+                    if let Some(value) = line.strip_prefix("Build fingerprint: ") {
+                        crash.fingerprint = Some(unquote(value).to_owned());
+                    } else if let Some(value) = line.strip_prefix("ABI: ") {
+                        crash.abi = Some(unquote(value).to_owned());
+                    } else if let Some(value) = line.strip_prefix("Abort message: ") {
+                        crash.abort_message = Some(unquote(value).to_owned());
+                    } else if let Some(value) = line.strip_prefix("Cause: ") {
+                        crash.cause = Some(value.to_owned());
+                    } else if line.starts_with("pid: ") {
+                        crash.parse_pid_line(line);
+                    } else if line.starts_with("signal ") {
+                        has_signal = crash.parse_signal_line(line).is_some();
+                    } else if let Some(registers) = parse_registers(line) {
+                        crash.registers.extend(registers);
+                    }
+                }
+                Section::Backtrace => match NativeFrame::parse(line) {
+                    Some(frame) => crash.frames.push(frame),
+                    None => section = Section::Other,
+                },
+                Section::Other => {}
+            }
+        }
+
+        if has_signal {
+            Some(crash)
+        } else {
+            None
+        }
+    }
+
+    // pid: 1234, tid: 1250, name: Thread-2  >>> com.example <<<
+    fn parse_pid_line(&mut self, line: &str) {
+        let (fields, process) = match line.split_once(">>>") {
+            Some((fields, process)) => (fields, Some(process)),
+            None => (line, None),
+        };
+
+        // The thread name is last and may contain ", ".
+        let (fields, name) = match fields.split_once(", name: ") {
+            Some((fields, name)) => (fields, Some(name.trim())),
+            None => (fields, None),
+        };
+        for field in fields.split(',') {
+            match field.trim().split_once(": ") {
+                Some(("pid", value)) => self.pid = value.parse().ok(),
+                Some(("tid", value)) => self.tid = value.parse().ok(),
+                _ => {}
+            }
+        }
+
+        self.thread_name = name.map(str::to_owned);
+        self.process_name = process
+            .and_then(|process| process.split_once("<<<"))
+            .map(|(process, _)| process.trim().to_owned());
+    }
+
+    // signal 11 (SIGSEGV), code 1 (SEGV_MAPERR), fault addr 0x0
+    fn parse_signal_line(&mut self, line: &str) -> Option<()> {
+        let (signal, rest) = line.strip_prefix("signal ")?.split_once(' ')?;
+        self.signal = signal.parse().ok()?;
+
+        let (name, rest) = rest.strip_prefix('(')?.split_once(')')?;
+        self.signal_name = Some(name.to_owned());
+
+        if let Some(rest) = rest.strip_prefix(", code ") {
+            if let Some((code, rest)) = rest.split_once(' ') {
+                self.code = code.parse().ok();
+                if let Some((name, _)) = rest.strip_prefix('(').and_then(|r| r.split_once(')')) {
+                    self.code_name = Some(name.to_owned());
+                }
+            }
+        }
+
+        if let Some((_, address)) = line.rsplit_once("fault addr ") {
+            let address = address.trim();
+            let address = address.strip_prefix("0x").unwrap_or(address);
+            self.fault_address = u64::from_str_radix(address, 16).ok();
+        }
+        Some(())
+    }
+
+    /// Returns the date and time of the first line of the report.
+    ///
+    /// Returns `None` if the report was not read from messages.
+    pub fn date_time(&self) -> Option<NaiveDateTime> {
+        self.date_time
+    }
+
+    /// Returns the build fingerprint of the device.
+    ///
+    /// Returns `None` if the fingerprint is not available.
+    pub fn fingerprint(&self) -> Option<&str> {
+        self.fingerprint.as_deref()
+    }
+
+    /// Returns the ABI of the crashed process, such as `arm64`.
+    ///
+    /// Returns `None` if the ABI is not available.
+    pub fn abi(&self) -> Option<&str> {
+        self.abi.as_deref()
+    }
+
+    /// Returns the process ID of the crashed process.
+    ///
+    /// Returns `None` if the process ID is not available.
+    pub fn process_id(&self) -> Option<i32> {
+        self.pid
+    }
+
+    /// Returns the thread ID of the crashed thread.
+    ///
+    /// Returns `None` if the thread ID is not available.
+    pub fn thread_id(&self) -> Option<i32> {
+        self.tid
+    }
+
+    /// Returns the name of the crashed thread.
+    ///
+    /// Returns `None` if the name is not available.
+    pub fn thread_name(&self) -> Option<&str> {
+        self.thread_name.as_deref()
+    }
+
+    /// Returns the name of the crashed process.
+    ///
+    /// Returns `None` if the name is not available.
+    pub fn process_name(&self) -> Option<&str> {
+        self.process_name.as_deref()
+    }
+
+    /// Returns the signal number, such as `11`.
+    pub fn signal(&self) -> i32 {
+        self.signal
+    }
+
+    /// Returns the signal name, such as `SIGSEGV`.
+    ///
+    /// Returns `None` if the name is not available.
+    pub fn signal_name(&self) -> Option<&str> {
+        self.signal_name.as_deref()
+    }
+
+    /// Returns the signal code, such as `1`.
+    ///
+    /// Returns `None` if the code is not available.
+    pub fn code(&self) -> Option<i32> {
+        self.code
+    }
+
+    /// Returns the signal code name, such as `SEGV_MAPERR`.
+    ///
+    /// Returns `None` if the name is not available.
+    pub fn code_name(&self) -> Option<&str> {
+        self.code_name.as_deref()
+    }
+
+    /// Returns the fault address.
+    ///
+    /// Returns `None` if the signal has no fault address.
+    pub fn fault_address(&self) -> Option<u64> {
+        self.fault_address
+    }
+
+    /// Returns the abort message, such as one passed to `abort_message()`
+    /// or a failed `CHECK`.
+    ///
+    /// Returns `None` if there is no abort message.
+    pub fn abort_message(&self) -> Option<&str> {
+        self.abort_message.as_deref()
+    }
+
+    /// Returns the probable cause, such as `null pointer dereference`.
+    ///
+    /// Returns `None` if the cause is not available.
+    pub fn cause(&self) -> Option<&str> {
+        self.cause.as_deref()
+    }
+
+    /// Returns the register names and values of the crashed thread.
+    pub fn registers(&self) -> &[(String, u64)] {
+        &self.registers
+    }
+
+    /// Returns the value of the register `name`.
+    ///
+    /// Returns `None` if the register is not available.
+    pub fn register(&self, name: &str) -> Option<u64> {
+        self.registers
+            .iter()
+            .find(|(register, _)| register == name)
+            .map(|&(_, value)| value)
+    }
+
+    /// Returns the backtrace of the crashed thread, innermost first.
+    pub fn frames(&self) -> &[NativeFrame] {
+        &self.frames
+    }
+}
+
+fn unquote(value: &str) -> &str {
+    value
+        .strip_prefix('\'')
+        .and_then(|value| value.strip_suffix('\''))
+        .unwrap_or(value)
+}
+
+// x0  0000000000000000  x1  00000000000004e2  x2  0000000000000006
+fn parse_registers(line: &str) -> Option<Vec<(String, u64)>> {
+    let tokens: Vec<_> = line.split_whitespace().collect();
+    if tokens.is_empty() || tokens.len() % 2 != 0 {
+        return None;
+    }
+
+    tokens
+        .chunks(2)
+        .map(|pair| {
+            let (name, value) = (pair[0], pair[1]);
+            // Not `tagged_addr_ctrl: 0000000000000001` and the like.
+            if !name.starts_with(|c: char| c.is_ascii_alphabetic())
+                || !name.chars().all(|c| c.is_ascii_alphanumeric())
+                || value.len() < 8
+            {
+                return None;
+            }
+            let value = u64::from_str_radix(value, 16).ok()?;
+            Some((name.to_owned(), value))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{analyze, analyze::NativeCrash, parse};
+
+    const CRASH: &str = "\
+01-02 10:00:00.000  5000  5000 F DEBUG   : *** *** *** *** *** *** *** *** *** *** *** *** *** *** *** ***
+01-02 10:00:00.000  5000  5000 F DEBUG   : Build fingerprint: 'google/sunfish/sunfish:11/RQ3A/7092808:user/release-keys'
+01-02 10:00:00.000  5000  5000 F DEBUG   : ABI: 'arm64'
+01-02 10:00:00.000  5000  5000 F DEBUG   : pid: 1234, tid: 1250, name: Thread-2  >>> com.example <<<
+01-02 10:00:00.000  1234  1250 I Example : unrelated
+01-02 10:00:00.000  5000  5000 F DEBUG   : signal 6 (SIGABRT), code -6 (SI_TKILL), fault addr --------
+01-02 10:00:00.000  5000  5000 F DEBUG   : Abort message: 'bad state'
+01-02 10:00:00.000  5000  5000 F DEBUG   :     tagged_addr_ctrl: 0000000000000001
+01-02 10:00:00.000  5000  5000 F DEBUG   :     x0  0000000000000000  x1  00000000000004e2
+01-02 10:00:00.000  5000  5000 F DEBUG   :     sp  0000007fc0d4f290  lr  00000074c3a8a8e0  pc  00000074c3a8a900
+01-02 10:00:00.000  5000  5000 F DEBUG   :
+01-02 10:00:00.000  5000  5000 F DEBUG   : backtrace:
+01-02 10:00:00.000  5000  5000 F DEBUG   :       #00 pc 000000000008a900  /apex/com.android.runtime/lib64/bionic/libc.so (abort+160) (BuildId: 5812256023147338b8a9538321d4c456)
+01-02 10:00:00.000  5000  5000 F DEBUG   :       #01 pc 0000000000001234  /data/app/base.apk (offset 0x1000) (Foo::bar(int)+12)
+01-02 10:00:00.000  5000  5000 F DEBUG   :       #02 pc 0000000000005678  /data/app/lib/arm64/libfoo.so
+01-02 10:00:00.000  5000  5000 F DEBUG   :
+01-02 10:00:00.000  5000  5000 F DEBUG   : memory near x1:
+01-02 10:00:00.000  5000  5000 F DEBUG   :     00000000000004e0 0000000000000000 0000000000000000  ................
+01-02 10:00:01.000  5001  5001 F DEBUG   : *** *** *** *** *** *** *** *** *** *** *** *** *** *** *** ***
+01-02 10:00:01.000  5001  5001 F DEBUG   : pid: 99, tid: 99, name: foo  >>> /system/bin/foo <<<
+01-02 10:00:01.000  5001  5001 F DEBUG   : signal 11 (SIGSEGV), code 1 (SEGV_MAPERR), fault addr 0x0
+01-02 10:00:01.000  5001  5001 F DEBUG   : Cause: null pointer dereference
+";
+
+    #[test]
+    fn native_crashes() {
+        let messages = CRASH.lines().map(|line| parse::threadtime(line).unwrap());
+        let crashes: Vec<_> = analyze::native_crashes(messages).collect();
+        assert_eq!(crashes.len(), 2);
+
+        let crash = &crashes[0];
+        assert!(crash.date_time().is_some());
+        assert_eq!(
+            crash.fingerprint(),
+            Some("google/sunfish/sunfish:11/RQ3A/7092808:user/release-keys")
+        );
+        assert_eq!(crash.abi(), Some("arm64"));
+        assert_eq!(crash.process_id(), Some(1234));
+        assert_eq!(crash.thread_id(), Some(1250));
+        assert_eq!(crash.thread_name(), Some("Thread-2"));
+        assert_eq!(crash.process_name(), Some("com.example"));
+        assert_eq!(crash.signal(), 6);
+        assert_eq!(crash.signal_name(), Some("SIGABRT"));
+        assert_eq!(crash.code(), Some(-6));
+        assert_eq!(crash.code_name(), Some("SI_TKILL"));
+        assert_eq!(crash.fault_address(), None);
+        assert_eq!(crash.abort_message(), Some("bad state"));
+        assert_eq!(crash.registers().len(), 5);
+        assert_eq!(crash.register("x1"), Some(0x4e2));
+        assert_eq!(crash.register("pc"), Some(0x74c3a8a900));
+        assert_eq!(crash.register("tagged_addr_ctrl"), None);
+
+        let frames = crash.frames();
+        assert_eq!(frames.len(), 3);
+        assert_eq!(frames[0].index(), 0);
+        assert_eq!(frames[0].pc(), 0x8a900);
+        assert_eq!(
+            frames[0].library(),
+            "/apex/com.android.runtime/lib64/bionic/libc.so"
+        );
+        assert_eq!(frames[0].symbol(), Some("abort"));
+        assert_eq!(frames[0].symbol_offset(), Some(160));
+        assert_eq!(
+            frames[0].build_id(),
+            Some("5812256023147338b8a9538321d4c456")
+        );
+        assert_eq!(frames[1].library(), "/data/app/base.apk");
+        assert_eq!(frames[1].library_offset(), Some(0x1000));
+        assert_eq!(frames[1].symbol(), Some("Foo::bar(int)"));
+        assert_eq!(frames[1].symbol_offset(), Some(12));
+        assert_eq!(frames[2].library(), "/data/app/lib/arm64/libfoo.so");
+        assert_eq!(frames[2].symbol(), None);
+
+        let crash = &crashes[1];
+        assert_eq!(crash.process_name(), Some("/system/bin/foo"));
+        assert_eq!(crash.signal_name(), Some("SIGSEGV"));
+        assert_eq!(crash.fault_address(), Some(0));
+        assert_eq!(crash.cause(), Some("null pointer dereference"));
+        assert!(crash.frames().is_empty());
+    }
+
+    #[test]
+    fn not_native_crash() {
+        assert!(NativeCrash::parse("").is_none());
+        assert!(NativeCrash::parse("pid: 1, tid: 1, name: foo  >>> foo <<<").is_none());
+    }
+}
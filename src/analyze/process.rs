@@ -0,0 +1,425 @@
+use crate::message::Message;
+use chrono::NaiveDateTime;
+use std::collections::HashMap;
+
+/// The lifetime of a process, as seen by a [`ProcessTracker`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProcessLifetime {
+    pid: i32,
+    name: String,
+    uid: Option<i32>,
+    start: Option<NaiveDateTime>,
+    end: Option<NaiveDateTime>,
+}
+
+impl ProcessLifetime {
+    /// Returns the process ID.
+    pub fn process_id(&self) -> i32 {
+        self.pid
+    }
+
+    /// Returns the process name, such as `com.example` or
+    /// `com.example:service`.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the user ID the process runs as.
+    ///
+    /// Returns `None` if the user ID is not available.
+    pub fn uid(&self) -> Option<i32> {
+        self.uid
+    }
+
+    /// Returns the date and time the process was started.
+    ///
+    /// If the start was not seen, returns the time the previous process with
+    /// the same process ID ended, or the time the process ID was first seen.
+    /// Returns `None` if neither is known.
+    pub fn start(&self) -> Option<NaiveDateTime> {
+        self.start
+    }
+
+    /// Returns the date and time the process was killed or died.
+    ///
+    /// Returns `None` if the process is still running.
+    pub fn end(&self) -> Option<NaiveDateTime> {
+        self.end
+    }
+
+    fn contains(&self, date_time: NaiveDateTime) -> bool {
+        self.start.map_or(true, |start| start <= date_time)
+            && self.end.map_or(true, |end| date_time <= end)
+    }
+}
+
+/// A change of a process, as returned by [`ProcessTracker::track_change`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ProcessChange {
+    /// A process was started.
+    Started(ProcessLifetime),
+    /// A process was killed or died.
+    Ended(ProcessLifetime),
+}
+
+/// Tracks process starts and deaths to map process IDs to process names.
+///
+/// Process lifetimes are learned from `ActivityManager` messages such as
+/// `Start proc`, `Killing` and `has died`, and from the `am_proc_start`,
+/// `am_proc_died` and `am_kill` events. Because process IDs are reused, each
+/// process ID maps to a list of lifetimes.
+///
+/// # Examples
+///
+/// ```
+/// use logcat::{analyze::ProcessTracker, parse};
+///
+/// let source = "...";
+/// let mut tracker = ProcessTracker::new();
+/// for line in source.lines() {
+///     if let Ok(msg) = parse::threadtime(line) {
+///         tracker.track(&msg);
+///         if let Some(name) = tracker.process_name_for(&msg) {
+///             println!("{}: {}", name, msg.content());
+///         }
+///     }
+/// }
+/// ```
+#[derive(Debug, Default)]
+pub struct ProcessTracker {
+    processes: HashMap<i32, Vec<ProcessLifetime>>,
+    first_seen: HashMap<i32, NaiveDateTime>,
+}
+
+impl ProcessTracker {
+    /// Creates a new ProcessTracker.
+    pub fn new() -> ProcessTracker {
+        ProcessTracker::default()
+    }
+
+    /// Updates the tracked processes from `message`.
+    ///
+    /// Messages that do not describe a process start or death are ignored.
+    pub fn track(&mut self, message: &Message) {
+        self.track_change(message);
+    }
+
+    /// Updates the tracked processes from `message`, as [`track`] does, and
+    /// returns the process that was started or ended.
+    ///
+    /// Returns `None` for messages that do not describe a process start or
+    /// death, and for repeated reports of the same start or death.
+    ///
+    /// [`track`]: ProcessTracker::track
+    pub fn track_change(&mut self, message: &Message) -> Option<ProcessChange> {
+        let date_time = message.date_time();
+        if let (Some(pid), Some(date_time)) = (message.process_id(), date_time) {
+            self.first_seen.entry(pid).or_insert(date_time);
+        }
+        match message.tag() {
+            "ActivityManager" => {
+                let content = message.content();
+                if let Some(rest) = content.strip_prefix("Start proc ") {
+                    if let Some((pid, name, uid)) = parse_start_proc(rest) {
+                        return self.start(pid, name, uid, date_time);
+                    }
+                } else if let Some(rest) = content.strip_prefix("Killing ") {
+                    let process = rest.split_whitespace().next().unwrap_or_default();
+                    if let Some((pid, name, _)) = parse_pid_process(process) {
+                        return self.end(pid, name, date_time);
+                    }
+                } else if let Some(rest) = content.strip_prefix("Process ") {
+                    // Process com.example (pid 1234) has died
+                    if let Some((name, rest)) = rest.split_once(" (pid ") {
+                        if let Some((pid, rest)) = rest.split_once(')') {
+                            if rest.trim_start().starts_with("has died") {
+                                if let Ok(pid) = pid.parse() {
+                                    return self.end(pid, name, date_time);
+                                }
+                            }
+                        }
+                    }
+                }
+                None
+            }
+            "am_proc_start" => {
+                // [User, PID, UID, Process Name, Type, Component]
+                if let Some(fields) = event_fields(message.content()) {
+                    if let (Some(pid), Some(uid), Some(name)) =
+                        (fields.get(1), fields.get(2), fields.get(3))
+                    {
+                        if let Ok(pid) = pid.parse() {
+                            return self.start(pid, name, uid.parse().ok(), date_time);
+                        }
+                    }
+                }
+                None
+            }
+            "am_proc_died" | "am_kill" => {
+                // [User, PID, Process Name, ...]
+                if let Some(fields) = event_fields(message.content()) {
+                    if let (Some(pid), Some(name)) = (fields.get(1), fields.get(2)) {
+                        if let Ok(pid) = pid.parse() {
+                            return self.end(pid, name, date_time);
+                        }
+                    }
+                }
+                None
+            }
+            _ => None,
+        }
+    }
+
+    fn start(
+        &mut self,
+        pid: i32,
+        name: &str,
+        uid: Option<i32>,
+        date_time: Option<NaiveDateTime>,
+    ) -> Option<ProcessChange> {
+        let lifetimes = self.processes.entry(pid).or_default();
+        if let Some(last) = lifetimes.last_mut() {
+            if last.end.is_none() {
+                // The same start is logged as a message and as an event.
+                if last.name == name {
+                    last.uid = last.uid.or(uid);
+                    return None;
+                }
+                last.end = date_time;
+            }
+        }
+
+        let lifetime = ProcessLifetime {
+            pid,
+            name: name.to_owned(),
+            uid,
+            start: date_time,
+            end: None,
+        };
+        lifetimes.push(lifetime.clone());
+        Some(ProcessChange::Started(lifetime))
+    }
+
+    fn end(
+        &mut self,
+        pid: i32,
+        name: &str,
+        date_time: Option<NaiveDateTime>,
+    ) -> Option<ProcessChange> {
+        let lifetimes = self.processes.entry(pid).or_default();
+        if let Some(last) = lifetimes.last_mut() {
+            if last.name == name {
+                // A process is usually reported as killed, then as died.
+                let ended = last.end.is_none();
+                if ended || last.end < date_time {
+                    last.end = date_time;
+                }
+                return ended.then(|| ProcessChange::Ended(last.clone()));
+            }
+            if last.end.is_none() {
+                last.end = date_time;
+            }
+        }
+
+        // The start was not seen, so the process started at the latest when
+        // the previous process ended, or when the process ID was first seen.
+        let start = match lifetimes.last() {
+            Some(last) => last.end,
+            None => self.first_seen.get(&pid).copied(),
+        };
+        let lifetime = ProcessLifetime {
+            pid,
+            name: name.to_owned(),
+            uid: None,
+            start,
+            end: date_time,
+        };
+        lifetimes.push(lifetime.clone());
+        Some(ProcessChange::Ended(lifetime))
+    }
+
+    /// Returns the name of the process that logged `message`.
+    ///
+    /// Returns `None` if the process is unknown.
+    pub fn process_name_for(&self, message: &Message) -> Option<&str> {
+        self.process_name(message.process_id()?, message.date_time())
+    }
+
+    /// Returns the name of the process with the process ID `pid` at
+    /// `date_time`, or the most recent process with that ID if `date_time`
+    /// is `None`.
+    ///
+    /// Returns `None` if the process is unknown.
+    pub fn process_name(&self, pid: i32, date_time: Option<NaiveDateTime>) -> Option<&str> {
+        self.lifetime(pid, date_time).map(ProcessLifetime::name)
+    }
+
+    /// Returns the lifetime of the process with the process ID `pid` at
+    /// `date_time`, or the most recent process with that ID if `date_time`
+    /// is `None`.
+    ///
+    /// Returns `None` if the process is unknown.
+    pub fn lifetime(&self, pid: i32, date_time: Option<NaiveDateTime>) -> Option<&ProcessLifetime> {
+        let lifetimes = self.processes.get(&pid)?;
+        match date_time {
+            Some(date_time) => lifetimes
+                .iter()
+                .rev()
+                .find(|lifetime| lifetime.contains(date_time)),
+            None => lifetimes.last(),
+        }
+    }
+
+    /// Returns all lifetimes of processes with the process ID `pid`, oldest
+    /// first.
+    pub fn lifetimes(&self, pid: i32) -> &[ProcessLifetime] {
+        self.processes.get(&pid).map_or(&[], Vec::as_slice)
+    }
+}
+
+// 1234:com.example/u0a12 for activity {com.example/com.example.Main}
+// com.example for activity com.example/.Main: pid=1234 uid=10012 gids={...}
+fn parse_start_proc(rest: &str) -> Option<(i32, &str, Option<i32>)> {
+    let process = rest.split_whitespace().next()?;
+    if let Some(process) = parse_pid_process(process) {
+        return Some(process);
+    }
+
+    let mut pid = None;
+    let mut uid = None;
+    for field in rest.split_whitespace() {
+        if let Some(value) = field.strip_prefix("pid=") {
+            pid = value.parse().ok();
+        } else if let Some(value) = field.strip_prefix("uid=") {
+            uid = value.parse().ok();
+        }
+    }
+    Some((pid?, process, uid))
+}
+
+// 1234:com.example/u0a12
+fn parse_pid_process(process: &str) -> Option<(i32, &str, Option<i32>)> {
+    let (pid, process) = process.split_once(':')?;
+    let pid = pid.parse().ok()?;
+    let (name, uid) = match process.rsplit_once('/') {
+        Some((name, uid)) => (name, parse_uid(uid)),
+        None => (process, None),
+    };
+    Some((pid, name, uid))
+}
+
+// 1000, u0a12, u10a12
+fn parse_uid(uid: &str) -> Option<i32> {
+    if let Ok(uid) = uid.parse() {
+        return Some(uid);
+    }
+
+    let (user, app) = uid.strip_prefix('u')?.split_once('a')?;
+    let user: i32 = user.parse().ok()?;
+    let app: i32 = app.parse().ok()?;
+    Some(user * 100_000 + 10_000 + app)
+}
+
+fn event_fields(content: &str) -> Option<Vec<&str>> {
+    let fields = content.trim().strip_prefix('[')?.strip_suffix(']')?;
+    Some(fields.split(',').collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        analyze::{ProcessChange, ProcessTracker},
+        parse,
+    };
+
+    const LOG: &str = "\
+01-02 10:00:00.000   567   600 I am_proc_start: [0,1234,10012,com.example,activity,{com.example/com.example.Main}]
+01-02 10:00:00.000   567   600 I ActivityManager: Start proc 1234:com.example/u0a12 for activity {com.example/com.example.Main}
+01-02 10:00:01.000  1234  1234 I Example : started
+01-02 10:00:02.000   567   600 I ActivityManager: Killing 1234:com.example/u0a12 (adj 900): empty #17
+01-02 10:00:02.100   567   610 I ActivityManager: Process com.example (pid 1234) has died: cch+5 CEM
+01-02 10:00:03.000   567   600 I ActivityManager: Start proc com.other for service com.other/.Service: pid=1234 uid=10013 gids={50013}
+01-02 10:00:04.000  1234  1234 I Other   : started
+01-02 10:00:05.000   567   600 I am_proc_died: [0,42,com.old,900,17]
+";
+
+    #[test]
+    fn process_names() {
+        let messages: Vec<_> = LOG
+            .lines()
+            .map(|line| parse::threadtime(line).unwrap())
+            .collect();
+
+        let mut tracker = ProcessTracker::new();
+        let mut names = Vec::new();
+        let mut changes = Vec::new();
+        for msg in &messages {
+            changes.push(tracker.track_change(msg));
+            names.push(tracker.process_name_for(msg).map(str::to_owned));
+        }
+        assert_eq!(names[2].as_deref(), Some("com.example"));
+        assert_eq!(names[6].as_deref(), Some("com.other"));
+
+        // Lookups after the fact use the message time.
+        assert_eq!(tracker.process_name_for(&messages[2]), Some("com.example"));
+        assert_eq!(tracker.process_name_for(&messages[6]), Some("com.other"));
+        assert_eq!(tracker.process_name(1234, None), Some("com.other"));
+
+        let lifetimes = tracker.lifetimes(1234);
+        assert_eq!(lifetimes.len(), 2);
+        assert_eq!(lifetimes[0].uid(), Some(10012));
+        assert_eq!(lifetimes[0].start(), messages[0].date_time());
+        assert_eq!(lifetimes[0].end(), messages[4].date_time());
+        assert_eq!(lifetimes[1].uid(), Some(10013));
+        assert_eq!(lifetimes[1].end(), None);
+
+        let lifetimes = tracker.lifetimes(42);
+        assert_eq!(lifetimes.len(), 1);
+        assert_eq!(lifetimes[0].name(), "com.old");
+        assert_eq!(lifetimes[0].start(), None);
+        assert_eq!(tracker.process_name_for(&messages[0]), None);
+
+        // A death without a start begins when the process ID was first seen,
+        // or when the previous process ended.
+        let messages: Vec<_> = [
+            "01-02 10:00:00.000  1234  1234 I Example : running",
+            "01-02 10:00:01.000   567   600 I am_proc_died: [0,1234,com.example,900,17]",
+            "01-02 10:00:02.000   567   600 I am_proc_died: [0,1234,com.other,900,17]",
+        ]
+        .into_iter()
+        .map(|line| parse::threadtime(line).unwrap())
+        .collect();
+        let mut tracker = ProcessTracker::new();
+        for msg in &messages {
+            tracker.track(msg);
+        }
+        let lifetimes = tracker.lifetimes(1234);
+        assert_eq!(lifetimes.len(), 2);
+        assert_eq!(lifetimes[0].start(), messages[0].date_time());
+        assert_eq!(lifetimes[1].start(), messages[1].date_time());
+        assert_eq!(lifetimes[1].end(), messages[2].date_time());
+
+        // Repeated reports of a start or death are not changes.
+        let changes: Vec<_> = changes
+            .iter()
+            .map(|change| match change {
+                Some(ProcessChange::Started(lifetime)) => format!("+{}", lifetime.name()),
+                Some(ProcessChange::Ended(lifetime)) => format!("-{}", lifetime.name()),
+                None => String::new(),
+            })
+            .collect();
+        assert_eq!(
+            changes,
+            [
+                "+com.example",
+                "",
+                "",
+                "-com.example",
+                "",
+                "+com.other",
+                "",
+                "-com.old"
+            ]
+        );
+    }
+}
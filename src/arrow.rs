@@ -0,0 +1,276 @@
+//! Columnar export of messages as Arrow record batches and Parquet files.
+//!
+//! Record batches have the following columns, in [`schema`] order:
+//!
+//! | Column    | Type                          | Nullable |
+//! |-----------|-------------------------------|----------|
+//! | `time`    | `Timestamp(Nanosecond, None)` | yes      |
+//! | `level`   | `Dictionary(Int8, Utf8)`      | no       |
+//! | `tag`     | `Dictionary(Int32, Utf8)`     | no       |
+//! | `pid`     | `Int32`                       | yes      |
+//! | `tid`     | `Int32`                       | yes      |
+//! | `uid`     | `Int32`                       | yes      |
+//! | `buffer`  | `Dictionary(Int8, Utf8)`      | yes      |
+//! | `content` | `Utf8`                        | no       |
+//!
+//! Levels are short descriptions, such as `W`. Timestamps are in the local
+//! time of the device, without a time zone.
+//!
+//! # Examples
+//!
+//! ```
+//! use logcat::{arrow::ParquetWriter, parse};
+//!
+//! let line = "08-01 07:30:00.123  1234  1250 W tag     : content";
+//! let msg = parse::threadtime(line).unwrap();
+//!
+//! let mut writer = ParquetWriter::new(Vec::new()).unwrap();
+//! writer.write(&msg).unwrap();
+//! let parquet = writer.finish().unwrap();
+//! assert!(parquet.starts_with(b"PAR1"));
+//! ```
+
+use crate::message::Message;
+use arrow_array::{
+    builder::{Int32Builder, StringBuilder, StringDictionaryBuilder, TimestampNanosecondBuilder},
+    types::{Int32Type, Int8Type},
+    ArrayRef, RecordBatch,
+};
+use arrow_schema::{ArrowError, DataType, Field, Schema, SchemaRef, TimeUnit};
+use parquet::{
+    arrow::ArrowWriter,
+    basic::{Compression, ZstdLevel},
+    errors::ParquetError,
+    file::properties::WriterProperties,
+};
+use std::io::Write;
+use std::sync::Arc;
+use thiserror::Error;
+
+/// The error type for columnar export.
+#[derive(Debug, Error)]
+pub enum Error {
+    /// Building a record batch failed.
+    #[error("arrow: {0}")]
+    Arrow(#[from] ArrowError),
+    /// Writing a Parquet file failed.
+    #[error("parquet: {0}")]
+    Parquet(#[from] ParquetError),
+}
+
+/// Returns the schema of record batches built from messages.
+pub fn schema() -> SchemaRef {
+    let dictionary = |key: DataType| DataType::Dictionary(Box::new(key), Box::new(DataType::Utf8));
+    Arc::new(Schema::new(vec![
+        Field::new(
+            "time",
+            DataType::Timestamp(TimeUnit::Nanosecond, None),
+            true,
+        ),
+        Field::new("level", dictionary(DataType::Int8), false),
+        Field::new("tag", dictionary(DataType::Int32), false),
+        Field::new("pid", DataType::Int32, true),
+        Field::new("tid", DataType::Int32, true),
+        Field::new("uid", DataType::Int32, true),
+        Field::new("buffer", dictionary(DataType::Int8), true),
+        Field::new("content", DataType::Utf8, false),
+    ]))
+}
+
+/// Converts messages into a record batch with the [`schema`].
+pub fn record_batch(messages: &[Message]) -> Result<RecordBatch, Error> {
+    let mut time = TimestampNanosecondBuilder::with_capacity(messages.len());
+    let mut level = StringDictionaryBuilder::<Int8Type>::new();
+    let mut tag = StringDictionaryBuilder::<Int32Type>::new();
+    let mut pid = Int32Builder::with_capacity(messages.len());
+    let mut tid = Int32Builder::with_capacity(messages.len());
+    let mut uid = Int32Builder::with_capacity(messages.len());
+    let mut buffer = StringDictionaryBuilder::<Int8Type>::new();
+    let mut content = StringBuilder::new();
+
+    for msg in messages {
+        time.append_option(
+            msg.date_time()
+                .and_then(|date_time| date_time.and_utc().timestamp_nanos_opt()),
+        );
+        level.append(msg.level().short())?;
+        tag.append(msg.tag())?;
+        pid.append_option(msg.process_id());
+        tid.append_option(msg.thread_id());
+        uid.append_option(msg.uid());
+        buffer.append_option(msg.buffer().map(|buffer| buffer.name()));
+        content.append_value(msg.content());
+    }
+
+    let columns: Vec<ArrayRef> = vec![
+        Arc::new(time.finish()),
+        Arc::new(level.finish()),
+        Arc::new(tag.finish()),
+        Arc::new(pid.finish()),
+        Arc::new(tid.finish()),
+        Arc::new(uid.finish()),
+        Arc::new(buffer.finish()),
+        Arc::new(content.finish()),
+    ];
+    Ok(RecordBatch::try_new(schema(), columns)?)
+}
+
+/// Writes messages into a Parquet file with the [`schema`].
+///
+/// Messages are buffered and written as one row group per
+/// [`batch_size`](ParquetWriter::batch_size) messages, so memory use does not
+/// grow with the number of messages. Columns are compressed with zstd.
+pub struct ParquetWriter<W: Write + Send> {
+    writer: ArrowWriter<W>,
+    batch: Vec<Message>,
+    batch_size: usize,
+}
+
+impl<W: Write + Send> ParquetWriter<W> {
+    /// Creates a new `ParquetWriter` writing to `inner`.
+    pub fn new(inner: W) -> Result<ParquetWriter<W>, Error> {
+        let properties = WriterProperties::builder()
+            .set_compression(Compression::ZSTD(ZstdLevel::default()))
+            .build();
+        Ok(ParquetWriter {
+            writer: ArrowWriter::try_new(inner, schema(), Some(properties))?,
+            batch: Vec::new(),
+            batch_size: 65536,
+        })
+    }
+
+    /// Sets the number of messages per row group.
+    pub fn batch_size(&mut self, value: usize) -> &mut Self {
+        self.batch_size = value.max(1);
+        self
+    }
+
+    /// Writes one message.
+    pub fn write(&mut self, msg: &Message) -> Result<(), Error> {
+        self.batch.push(msg.clone());
+        if self.batch.len() >= self.batch_size {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Writes all `messages`.
+    pub fn write_all<I: IntoIterator<Item = Message>>(&mut self, messages: I) -> Result<(), Error> {
+        for msg in messages {
+            self.batch.push(msg);
+            if self.batch.len() >= self.batch_size {
+                self.flush()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes the buffered messages as a row group.
+    pub fn flush(&mut self) -> Result<(), Error> {
+        if self.batch.is_empty() {
+            return Ok(());
+        }
+        let batch = record_batch(&self.batch)?;
+        self.batch.clear();
+        self.writer.write(&batch)?;
+        self.writer.flush()?;
+        Ok(())
+    }
+
+    /// Writes the buffered messages and the file footer, and returns the
+    /// underlying writer.
+    pub fn finish(mut self) -> Result<W, Error> {
+        self.flush()?;
+        Ok(self.writer.into_inner()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::arrow::{record_batch, schema, ParquetWriter};
+    use crate::message::{Buffer, Level, MessageBuilder};
+    use crate::parse;
+    use arrow_array::{
+        cast::AsArray,
+        types::{Int8Type, TimestampNanosecondType},
+        Array,
+    };
+    use parquet::{
+        arrow::arrow_reader::ParquetRecordBatchReaderBuilder,
+        basic::Compression,
+        file::reader::{FileReader, SerializedFileReader},
+    };
+    use std::fs::{self, File};
+
+    #[test]
+    fn arrow() {
+        let messages = vec![
+            parse::threadtime("2017-08-01 07:30:00.123456789  1234  1250 W tag     : one").unwrap(),
+            MessageBuilder::new()
+                .level(Level::Warning)
+                .tag("other")
+                .content("two")
+                .buffer(Buffer::Main)
+                .build()
+                .unwrap(),
+        ];
+
+        let batch = record_batch(&messages).unwrap();
+        assert_eq!(batch.schema(), schema());
+        assert_eq!(batch.num_rows(), 2);
+
+        let time = batch.column(0).as_primitive::<TimestampNanosecondType>();
+        assert_eq!(time.value(0), 1_501_572_600_123_456_789);
+        assert!(time.is_null(1));
+
+        let level = batch.column(1).as_dictionary::<Int8Type>();
+        assert_eq!(level.values().len(), 1);
+        assert_eq!(level.values().as_string::<i32>().value(0), "W");
+
+        assert!(batch.column(3).is_valid(0));
+        assert!(batch.column(3).is_null(1));
+        assert!(batch.column(6).is_null(0));
+        assert_eq!(batch.column(7).as_string::<i32>().value(1), "two");
+    }
+
+    #[test]
+    fn parquet() {
+        let messages = (0..10).map(|i| {
+            MessageBuilder::new()
+                .level(Level::Info)
+                .tag("tag")
+                .content(&format!("message {}", i))
+                .process_id(i)
+                .build()
+                .unwrap()
+        });
+
+        let dir = std::env::temp_dir().join(format!("logcat-parquet-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("logcat.parquet");
+        let mut writer = ParquetWriter::new(File::create(&path).unwrap()).unwrap();
+        writer.batch_size(4).write_all(messages).unwrap();
+        writer.finish().unwrap();
+
+        let reader = SerializedFileReader::new(File::open(&path).unwrap()).unwrap();
+        assert_eq!(reader.metadata().num_row_groups(), 3);
+        assert!(matches!(
+            reader.metadata().row_group(0).column(7).compression(),
+            Compression::ZSTD(_)
+        ));
+
+        let batches: Vec<_> = ParquetRecordBatchReaderBuilder::try_new(File::open(&path).unwrap())
+            .unwrap()
+            .build()
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        let rows: usize = batches.iter().map(|batch| batch.num_rows()).sum();
+        assert_eq!(rows, 10);
+        assert_eq!(
+            batches[0].column(7).as_string::<i32>().value(1),
+            "message 1"
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
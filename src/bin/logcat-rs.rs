@@ -0,0 +1,562 @@
+//! Filters and reformats Android logcat files.
+
+use anyhow::{bail, Context, Result};
+use logcat::{
+    csv, decompress,
+    filter::Filter,
+    format::{Format, Formatter},
+    message::{Level, Message},
+    parse::{self, MessageIterator, Parser},
+    pretty::PrettyPrinter,
+    query::Query,
+};
+use std::collections::HashMap;
+use std::env;
+use std::io::{self, BufRead, BufWriter, Write};
+use std::process;
+
+const USAGE: &str = "\
+Usage: logcat-rs [OPTIONS] [FILE]...
+
+Reads logcat messages from each FILE, or standard input if there is none or
+FILE is `-`, and writes the matching messages. Files compressed with gzip,
+zstd or xz are decompressed when built with the feature of the same name.
+
+Options:
+  -i, --input FORMAT    Input format: auto (default), threadtime or json
+  -o, --output FORMAT   Output format: a logcat format such as threadtime
+                        (default), brief or long, or pretty, json, csv or tsv
+      --color WHEN      Colors for pretty output: auto (default), always or
+                        never
+  -F, --filter SPEC     Filterspecs, such as `ActivityManager:I *:S`
+  -l, --level LEVEL     Minimum level, such as W or warning
+  -t, --tag TAG         Only messages with this tag (repeatable)
+  -p, --pid PID         Only messages of this process (repeatable)
+      --since TIME      Only messages at or after TIME
+      --until TIME      Only messages before TIME
+  -q, --query QUERY     Only messages matching a query, such as `level>=W`
+  -c, --count           Print the number of matching messages
+      --stats           Print statistics about the matching messages
+  -h, --help            Print this help
+
+TIME is `HH:MM[:SS[.fff]]` or `YYYY-MM-DD HH:MM[:SS[.fff]]`.";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Input {
+    Auto,
+    ThreadTime,
+    Json,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Output {
+    Logcat(Format),
+    Json,
+    Csv,
+    Tsv,
+    Pretty,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Color {
+    Auto,
+    Always,
+    Never,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Mode {
+    Print,
+    Count,
+    Stats,
+}
+
+#[derive(Debug)]
+struct Options {
+    files: Vec<String>,
+    input: Input,
+    output: Output,
+    filter: Option<Filter>,
+    level: Option<Level>,
+    tags: Vec<String>,
+    pids: Vec<i32>,
+    queries: Vec<Query>,
+    color: Color,
+    mode: Mode,
+}
+
+impl Options {
+    fn parse<I: IntoIterator<Item = String>>(args: I) -> Result<Options> {
+        let mut options = Options {
+            files: Vec::new(),
+            input: Input::Auto,
+            output: Output::Logcat(Format::ThreadTime),
+            filter: None,
+            level: None,
+            tags: Vec::new(),
+            pids: Vec::new(),
+            queries: Vec::new(),
+            color: Color::Auto,
+            mode: Mode::Print,
+        };
+
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            // Accept both `--option value` and `--option=value`.
+            let (name, inline) = match arg.split_once('=') {
+                Some((name, value)) if name.starts_with("--") => (name.to_owned(), Some(value)),
+                _ => (arg.clone(), None),
+            };
+            let mut value = || -> Result<String> {
+                match inline {
+                    Some(value) => Ok(value.to_owned()),
+                    None => args
+                        .next()
+                        .with_context(|| format!("missing value for `{}`", name)),
+                }
+            };
+
+            match name.as_str() {
+                "-i" | "--input" => {
+                    options.input = match value()?.as_str() {
+                        "auto" => Input::Auto,
+                        "threadtime" => Input::ThreadTime,
+                        "json" => Input::Json,
+                        other => bail!("invalid input format: `{}`", other),
+                    }
+                }
+                "-o" | "--output" => {
+                    options.output = match value()?.as_str() {
+                        "json" => Output::Json,
+                        "csv" => Output::Csv,
+                        "tsv" => Output::Tsv,
+                        "pretty" => Output::Pretty,
+                        other => Output::Logcat(other.parse()?),
+                    }
+                }
+                "-F" | "--filter" => {
+                    let filter = options.filter.get_or_insert_with(Filter::new);
+                    for expression in value()?.split(|c: char| c.is_whitespace() || c == ',') {
+                        if !expression.is_empty() {
+                            filter.add(expression)?;
+                        }
+                    }
+                }
+                "-l" | "--level" => options.level = Some(value()?.parse()?),
+                "-t" | "--tag" => options.tags.push(value()?),
+                "-p" | "--pid" => {
+                    let pid = value()?;
+                    options.pids.push(
+                        pid.parse()
+                            .with_context(|| format!("invalid pid: `{}`", pid))?,
+                    );
+                }
+                "--since" => options.queries.push(time_query(">=", &value()?)?),
+                "--until" => options.queries.push(time_query("<", &value()?)?),
+                "-q" | "--query" => options.queries.push(value()?.parse()?),
+                "--color" => {
+                    options.color = match value()?.as_str() {
+                        "auto" => Color::Auto,
+                        "always" => Color::Always,
+                        "never" => Color::Never,
+                        other => bail!("invalid color: `{}`", other),
+                    }
+                }
+                "-c" | "--count" => options.mode = Mode::Count,
+                "--stats" => options.mode = Mode::Stats,
+                "-h" | "--help" => {
+                    println!("{}", USAGE);
+                    process::exit(0);
+                }
+                "-" => options.files.push(arg),
+                _ if arg.starts_with('-') => bail!("unknown option: `{}`", arg),
+                _ => options.files.push(arg),
+            }
+        }
+
+        if options.mode != Mode::Print && !matches!(options.output, Output::Logcat(_)) {
+            bail!("`--count` and `--stats` cannot be combined with `--output`");
+        }
+        if cfg!(not(feature = "json"))
+            && (options.input == Input::Json || options.output == Output::Json)
+        {
+            bail!("JSON support requires the `json` feature");
+        }
+        Ok(options)
+    }
+
+    fn matches(&self, msg: &Message) -> bool {
+        self.filter
+            .as_ref()
+            .map_or(true, |filter| filter.matches(msg))
+            && self.level.map_or(true, |level| msg.level() >= level)
+            && (self.tags.is_empty() || self.tags.iter().any(|tag| tag == msg.tag()))
+            && (self.pids.is_empty()
+                || msg.process_id().is_some_and(|pid| self.pids.contains(&pid)))
+            && self.queries.iter().all(|query| query.matches(msg))
+    }
+}
+
+fn time_query(op: &str, time: &str) -> Result<Query> {
+    Query::parse(&format!("time {} \"{}\"", op, time))
+        .with_context(|| format!("invalid time: `{}`", time))
+}
+
+impl Parser for Input {
+    fn parse(&mut self, line: &str) -> Result<Message> {
+        let json = match self {
+            Input::Auto => line.trim_start().starts_with('{'),
+            Input::ThreadTime => false,
+            Input::Json => true,
+        };
+        if json {
+            #[cfg(feature = "json")]
+            return parse::json(line);
+        }
+        parse::threadtime(line)
+    }
+}
+
+enum Writer<W: Write> {
+    Logcat(Formatter, W),
+    Pretty(PrettyPrinter, W),
+    #[cfg(feature = "json")]
+    Json(logcat::json::Writer<W>),
+    Csv(csv::Writer<W>),
+}
+
+impl<W: Write> Writer<W> {
+    fn new(options: &Options, out: W) -> Writer<W> {
+        match options.output {
+            Output::Logcat(format) => Writer::Logcat(Formatter::new(format), out),
+            Output::Pretty => {
+                let printer = match options.color {
+                    Color::Auto => PrettyPrinter::for_stream(&io::stdout()),
+                    Color::Always => PrettyPrinter::new(),
+                    Color::Never => {
+                        let mut printer = PrettyPrinter::new();
+                        printer.color(false);
+                        printer
+                    }
+                };
+                Writer::Pretty(printer, out)
+            }
+            #[cfg(feature = "json")]
+            Output::Json => Writer::Json(logcat::json::Writer::new(out)),
+            #[cfg(not(feature = "json"))]
+            Output::Json => unreachable!("rejected when parsing options"),
+            Output::Csv => Writer::Csv(csv::Writer::new(out)),
+            Output::Tsv => Writer::Csv(csv::Writer::tsv(out)),
+        }
+    }
+
+    fn write(&mut self, msg: &Message) -> io::Result<()> {
+        match self {
+            Writer::Logcat(formatter, out) => formatter.write(out, msg),
+            Writer::Pretty(printer, out) => printer.write(out, msg),
+            #[cfg(feature = "json")]
+            Writer::Json(writer) => writer.write(msg),
+            Writer::Csv(writer) => writer.write(msg),
+        }
+    }
+
+    // Lets writers learn from messages that are filtered out.
+    fn skip(&mut self, msg: &Message) {
+        match self {
+            Writer::Pretty(printer, _) => printer.track(msg),
+            Writer::Csv(writer) => writer.track(msg),
+            _ => {}
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Writer::Logcat(_, out) | Writer::Pretty(_, out) => out.flush(),
+            #[cfg(feature = "json")]
+            Writer::Json(writer) => writer.flush(),
+            Writer::Csv(writer) => {
+                writer.write_all(std::iter::empty())?;
+                writer.flush()
+            }
+        }
+    }
+}
+
+#[derive(Default)]
+struct Stats {
+    count: usize,
+    levels: HashMap<Level, usize>,
+    tags: HashMap<String, usize>,
+    pids: HashMap<i32, usize>,
+    first: Option<Message>,
+    last: Option<Message>,
+}
+
+impl Stats {
+    fn add(&mut self, msg: &Message) {
+        self.count += 1;
+        *self.levels.entry(msg.level()).or_default() += 1;
+        *self.tags.entry(msg.tag().to_owned()).or_default() += 1;
+        if let Some(pid) = msg.process_id() {
+            *self.pids.entry(pid).or_default() += 1;
+        }
+        if msg.date_time().is_some() {
+            if self.first.is_none() {
+                self.first = Some(msg.clone());
+            }
+            self.last = Some(msg.clone());
+        }
+    }
+
+    fn write<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        writeln!(out, "messages: {}", self.count)?;
+        if let (Some(first), Some(last)) = (&self.first, &self.last) {
+            let first = first.date_time().unwrap_or_default();
+            let last = last.date_time().unwrap_or_default();
+            writeln!(out, "first: {}", first)?;
+            writeln!(out, "last: {}", last)?;
+        }
+
+        writeln!(out, "levels:")?;
+        let mut levels: Vec<_> = self.levels.iter().collect();
+        levels.sort();
+        for (level, count) in levels {
+            writeln!(out, "  {:#8} {}", level, count)?;
+        }
+
+        writeln!(out, "top tags:")?;
+        for (tag, count) in top(&self.tags) {
+            writeln!(out, "  {:8} {}", count, tag)?;
+        }
+
+        writeln!(out, "top processes:")?;
+        for (pid, count) in top(&self.pids) {
+            writeln!(out, "  {:8} {}", count, pid)?;
+        }
+        Ok(())
+    }
+}
+
+// The ten most frequent keys, most frequent first.
+fn top<K: Ord>(counts: &HashMap<K, usize>) -> Vec<(&K, usize)> {
+    let mut counts: Vec<_> = counts.iter().map(|(key, count)| (key, *count)).collect();
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+    counts.truncate(10);
+    counts
+}
+
+fn run<R: BufRead, W: Write>(options: &Options, inputs: Vec<R>, out: W) -> Result<()> {
+    let mut writer = Writer::new(options, out);
+    let mut stats = Stats::default();
+
+    for input in inputs {
+        for msg in MessageIterator::new(input, options.input) {
+            let msg = msg?;
+            if !options.matches(&msg) {
+                writer.skip(&msg);
+                continue;
+            }
+            match options.mode {
+                Mode::Print => writer.write(&msg)?,
+                Mode::Count | Mode::Stats => stats.add(&msg),
+            }
+        }
+    }
+
+    match (options.mode, writer) {
+        (Mode::Print, mut writer) => writer.flush()?,
+        (Mode::Count, Writer::Logcat(_, mut out)) => writeln!(out, "{}", stats.count)?,
+        (Mode::Stats, Writer::Logcat(_, mut out)) => stats.write(&mut out)?,
+        _ => unreachable!("rejected by `Options::parse`"),
+    }
+    Ok(())
+}
+
+fn main() {
+    let result = Options::parse(env::args().skip(1)).and_then(|options| {
+        let mut inputs: Vec<Box<dyn BufRead>> = Vec::new();
+        if options.files.is_empty() {
+            inputs.push(decompress::reader(io::stdin())?);
+        }
+        for path in &options.files {
+            if path == "-" {
+                inputs.push(decompress::reader(io::stdin())?);
+            } else {
+                let input =
+                    decompress::open(path).with_context(|| format!("failed to open `{}`", path))?;
+                inputs.push(input);
+            }
+        }
+        run(&options, inputs, BufWriter::new(io::stdout().lock()))
+    });
+
+    if let Err(e) = result {
+        // A closed pipe, such as from `| head`, is not an error.
+        if e.downcast_ref::<io::Error>()
+            .is_some_and(|e| e.kind() == io::ErrorKind::BrokenPipe)
+        {
+            return;
+        }
+        eprintln!("logcat-rs: {:#}", e);
+        process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{run, Input, Options, Output};
+    use logcat::format::Format;
+    use logcat::message::Level;
+
+    const DATA: &str = "\
+--------- beginning of main
+2017-08-01 07:30:00.000  1000  1020 I ActivityManager: Start proc 1234:com.example/u0a12 for activity com.example/.Main
+2017-08-01 07:30:01.000  1234  1250 W Example : low memory
+2017-08-01 07:30:02.000  1234  1250 E Example : out of memory
+2017-08-01 07:30:03.000  1234  1251 D Other   : done
+";
+
+    fn options(args: &[&str]) -> Options {
+        Options::parse(args.iter().map(|arg| arg.to_string())).unwrap()
+    }
+
+    fn output(args: &[&str]) -> String {
+        let mut out = Vec::new();
+        run(&options(args), vec![DATA.as_bytes()], &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn parse_options() {
+        let parsed = options(&[
+            "-i",
+            "threadtime",
+            "--output=csv",
+            "-l",
+            "warn",
+            "-p",
+            "1",
+            "a.log",
+            "-",
+        ]);
+        assert_eq!(parsed.input, Input::ThreadTime);
+        assert_eq!(parsed.output, Output::Csv);
+        assert_eq!(parsed.level, Some(Level::Warning));
+        assert_eq!(parsed.pids, [1]);
+        assert_eq!(parsed.files, ["a.log", "-"]);
+
+        assert_eq!(options_error(&["-o", "xml"]), "invalid format: `xml`");
+        assert_eq!(options_error(&["--bogus"]), "unknown option: `--bogus`");
+        assert_eq!(options_error(&["-t"]), "missing value for `-t`");
+        assert_eq!(
+            options_error(&["-c", "-o", "csv"]),
+            "`--count` and `--stats` cannot be combined with `--output`"
+        );
+        assert_eq!(
+            options_error(&["--since", "later"]),
+            "invalid time: `later`"
+        );
+        assert_eq!(
+            options(&["-o", "brief"]).output,
+            Output::Logcat(Format::Brief)
+        );
+    }
+
+    fn options_error(args: &[&str]) -> String {
+        Options::parse(args.iter().map(|arg| arg.to_string()))
+            .unwrap_err()
+            .to_string()
+    }
+
+    #[test]
+    fn filters() {
+        assert_eq!(output(&["-l", "E"]).lines().count(), 1);
+        assert_eq!(output(&["-t", "Example", "-t", "Other"]).lines().count(), 3);
+        assert_eq!(output(&["-p", "1000"]).lines().count(), 1);
+        assert_eq!(output(&["-F", "Example:E *:S"]).lines().count(), 1);
+        assert_eq!(
+            output(&["--since", "07:30:01", "--until", "2017-08-01 07:30:03"])
+                .lines()
+                .count(),
+            2
+        );
+        assert_eq!(
+            output(&["-q", "content contains memory"]).lines().count(),
+            2
+        );
+        assert_eq!(
+            output(&["-t", "Other"]),
+            "08-01 07:30:03.000  1234  1251 D Other   : done\n"
+        );
+    }
+
+    #[test]
+    fn outputs() {
+        assert_eq!(
+            output(&["-t", "Other", "-o", "brief"]),
+            "D/Other   ( 1234): done\n"
+        );
+        assert_eq!(
+            output(&["-t", "Other", "-o", "csv"]),
+            "time,level,pid,tid,uid,tag,content,buffer,process\n\
+             2017-08-01 07:30:03,D,1234,1251,,Other,done,,com.example\n"
+        );
+        assert_eq!(output(&["-t", "Nothing", "-o", "tsv"]).lines().count(), 1);
+        assert_eq!(
+            output(&["-t", "Other", "-o", "pretty", "--color", "never"]),
+            "                  Other  D  done\n"
+        );
+        #[cfg(feature = "json")]
+        assert!(output(&["-t", "Other", "-o", "json"]).starts_with(r#"{"level":"D","tag":"Other""#));
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn json_input() {
+        let json = output(&["-o", "json"]);
+        let mut out = Vec::new();
+        run(&options(&["-t", "Other"]), vec![json.as_bytes()], &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "08-01 07:30:03.000  1234  1251 D Other   : done\n"
+        );
+    }
+
+    #[test]
+    fn invalid_utf8() {
+        let data = b"08-01 07:30:00.000  1234  1250 I tag     : caf\xe9\n\
+                     08-01 07:30:01.000  1234  1250 I tag     : done\n";
+        let mut out = Vec::new();
+        run(&options(&[]), vec![&data[..]], &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "08-01 07:30:00.000  1234  1250 I tag     : caf\u{fffd}\n\
+             08-01 07:30:01.000  1234  1250 I tag     : done\n"
+        );
+    }
+
+    #[test]
+    fn count_and_stats() {
+        assert_eq!(output(&["--count", "-l", "W"]), "2\n");
+        assert_eq!(
+            output(&["--stats"]),
+            concat!(
+                "messages: 4\n",
+                "first: 2017-08-01 07:30:00\n",
+                "last: 2017-08-01 07:30:03\n",
+                "levels:\n",
+                "  Debug    1\n",
+                "  Info     1\n",
+                "  Warning  1\n",
+                "  Error    1\n",
+                "top tags:\n",
+                "         2 Example\n",
+                "         1 ActivityManager\n",
+                "         1 Other\n",
+                "top processes:\n",
+                "         3 1234\n",
+                "         1 1000\n",
+            )
+        );
+    }
+}
@@ -0,0 +1,610 @@
+//! Interactive terminal viewer for Android logcat files.
+
+use anyhow::{bail, Context, Result};
+use logcat::{
+    decompress,
+    format::Formatter,
+    message::{Level, Message},
+    parse::{self, MessageIterator, Parser},
+    query::Query,
+};
+use ratatui::{
+    crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    layout::{Constraint, Layout, Position, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph, Wrap},
+    Frame,
+};
+use std::env;
+use std::io::{self, BufRead};
+
+const USAGE: &str = "\
+Usage: logcat-tui FILE
+
+The whole file is loaded into memory, and filtering and searching scan every
+message, so very large files take a while to open and filter.";
+
+const HELP: &str = "j/k scroll  / search  n/N next/prev  f filter  2-7 toggle V..F  \
+                    t jump to time  enter details  q quit";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Prompt {
+    Search,
+    Filter,
+    Time,
+}
+
+impl Prompt {
+    fn label(self) -> &'static str {
+        match self {
+            Prompt::Search => "/",
+            Prompt::Filter => "filter: ",
+            Prompt::Time => "time: ",
+        }
+    }
+}
+
+struct App {
+    messages: Vec<Message>,
+    // Indices into `messages` of the messages passing the filters.
+    visible: Vec<usize>,
+    // Index into `visible` of the selected message.
+    selected: usize,
+    // Index into `visible` of the first row on screen.
+    offset: usize,
+    // Number of rows of the message list, as last drawn.
+    height: usize,
+    // Whether messages are shown, by level priority.
+    levels: [bool; 9],
+    filter: Query,
+    filter_text: String,
+    search: String,
+    // The prompt being edited, its text, and the selection when it opened.
+    prompt: Option<(Prompt, String, usize)>,
+    details: bool,
+    status: String,
+    quit: bool,
+}
+
+impl App {
+    fn new(messages: Vec<Message>) -> App {
+        let mut app = App {
+            visible: Vec::new(),
+            messages,
+            selected: 0,
+            offset: 0,
+            height: 20,
+            levels: [true; 9],
+            filter: Query::parse("").expect("the empty query is valid"),
+            filter_text: String::new(),
+            search: String::new(),
+            prompt: None,
+            details: false,
+            status: HELP.to_owned(),
+            quit: false,
+        };
+        app.refilter();
+        app
+    }
+
+    fn selected_message(&self) -> Option<&Message> {
+        self.visible.get(self.selected).map(|i| &self.messages[*i])
+    }
+
+    // Recomputes the visible messages, keeping the selected message or the
+    // nearest one after it selected.
+    fn refilter(&mut self) {
+        let current = self.visible.get(self.selected).copied().unwrap_or(0);
+        self.visible = (0..self.messages.len())
+            .filter(|i| {
+                let msg = &self.messages[*i];
+                self.levels[msg.level().priority() as usize] && self.filter.matches(msg)
+            })
+            .collect();
+        self.selected = self
+            .visible
+            .partition_point(|i| *i < current)
+            .min(self.visible.len().saturating_sub(1));
+        self.scroll_into_view();
+    }
+
+    fn select(&mut self, selected: usize) {
+        self.selected = selected.min(self.visible.len().saturating_sub(1));
+        self.scroll_into_view();
+    }
+
+    fn scroll_into_view(&mut self) {
+        let height = self.height.max(1);
+        if self.selected < self.offset {
+            self.offset = self.selected;
+        } else if self.selected >= self.offset + height {
+            self.offset = self.selected + 1 - height;
+        }
+    }
+
+    // Returns the first visible message from `start` in the direction of
+    // `forward` that contains the search text in its tag or content.
+    fn find(&self, start: usize, forward: bool) -> Option<usize> {
+        let needle = self.search.to_lowercase();
+        let matches = |i: &usize| {
+            let msg = &self.messages[self.visible[*i]];
+            msg.content().to_lowercase().contains(&needle)
+                || msg.tag().to_lowercase().contains(&needle)
+        };
+        if forward {
+            (start..self.visible.len()).find(matches)
+        } else {
+            (0..=start.min(self.visible.len().saturating_sub(1)))
+                .rev()
+                .find(matches)
+        }
+    }
+
+    fn search_next(&mut self, forward: bool) {
+        if self.search.is_empty() {
+            return;
+        }
+        let start = if forward {
+            self.selected + 1
+        } else {
+            self.selected.saturating_sub(1)
+        };
+        match self.find(start, forward) {
+            Some(i) => self.select(i),
+            None => self.status = format!("not found: {}", self.search),
+        }
+    }
+
+    fn handle_key(&mut self, key: KeyEvent) {
+        if key.kind == KeyEventKind::Release {
+            return;
+        }
+        if self.prompt.is_some() {
+            self.handle_prompt_key(key);
+            return;
+        }
+
+        let page = self.height.max(1);
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => self.quit = true,
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => self.quit = true,
+            KeyCode::Down | KeyCode::Char('j') => self.select(self.selected + 1),
+            KeyCode::Up | KeyCode::Char('k') => self.select(self.selected.saturating_sub(1)),
+            KeyCode::PageDown | KeyCode::Char(' ') => self.select(self.selected + page),
+            KeyCode::PageUp => self.select(self.selected.saturating_sub(page)),
+            KeyCode::Home | KeyCode::Char('g') => self.select(0),
+            KeyCode::End | KeyCode::Char('G') => self.select(usize::MAX),
+            KeyCode::Enter => self.details = !self.details,
+            KeyCode::Char('n') => self.search_next(true),
+            KeyCode::Char('N') => self.search_next(false),
+            KeyCode::Char('/') => self.open_prompt(Prompt::Search, String::new()),
+            KeyCode::Char('f') => self.open_prompt(Prompt::Filter, self.filter_text.clone()),
+            KeyCode::Char('t') => self.open_prompt(Prompt::Time, String::new()),
+            KeyCode::Char('?') => self.status = HELP.to_owned(),
+            KeyCode::Char(c @ '2'..='7') => {
+                let priority = c as usize - '0' as usize;
+                self.levels[priority] = !self.levels[priority];
+                self.refilter();
+                self.status = format!("levels: {}", self.levels_summary());
+            }
+            _ => {}
+        }
+    }
+
+    fn open_prompt(&mut self, prompt: Prompt, text: String) {
+        self.prompt = Some((prompt, text, self.selected));
+        self.status.clear();
+    }
+
+    fn handle_prompt_key(&mut self, key: KeyEvent) {
+        let Some((prompt, mut text, origin)) = self.prompt.take() else {
+            return;
+        };
+        match key.code {
+            KeyCode::Esc => {
+                // Undo the effects of editing.
+                match prompt {
+                    Prompt::Search => self.select(origin),
+                    Prompt::Filter => {
+                        let filter_text = self.filter_text.clone();
+                        self.apply_filter(&filter_text);
+                    }
+                    Prompt::Time => {}
+                }
+                return;
+            }
+            KeyCode::Enter => {
+                match prompt {
+                    Prompt::Search => self.search = text,
+                    Prompt::Filter => {
+                        if self.apply_filter(&text) {
+                            self.filter_text = text;
+                        } else {
+                            self.prompt = Some((prompt, text, origin));
+                        }
+                    }
+                    Prompt::Time => {
+                        if let Err(e) = self.jump_to_time(&text) {
+                            self.status = e.to_string();
+                            self.prompt = Some((prompt, text, origin));
+                        }
+                    }
+                }
+                return;
+            }
+            KeyCode::Backspace => {
+                text.pop();
+            }
+            KeyCode::Char(c) => text.push(c),
+            _ => {}
+        }
+
+        // Searching and filtering are live while typing.
+        match prompt {
+            Prompt::Search => {
+                self.search = text.clone();
+                if let Some(i) = self.find(origin, true) {
+                    self.select(i);
+                    self.status.clear();
+                } else {
+                    self.status = "not found".to_owned();
+                }
+            }
+            Prompt::Filter => {
+                self.apply_filter(&text);
+            }
+            Prompt::Time => {}
+        }
+        self.prompt = Some((prompt, text, origin));
+    }
+
+    // Applies the query in `text`, and returns whether it is valid.
+    fn apply_filter(&mut self, text: &str) -> bool {
+        match Query::parse(text) {
+            Ok(query) => {
+                self.filter = query;
+                self.refilter();
+                self.status = format!("{} of {} messages", self.visible.len(), self.messages.len());
+                true
+            }
+            Err(e) => {
+                self.status = e.to_string();
+                false
+            }
+        }
+    }
+
+    fn jump_to_time(&mut self, text: &str) -> Result<()> {
+        let query = Query::parse(&format!("time >= \"{}\"", text))
+            .with_context(|| format!("invalid time: {}", text))?;
+        match (0..self.visible.len()).find(|i| query.matches(&self.messages[self.visible[*i]])) {
+            Some(i) => self.select(i),
+            None => bail!("no messages at or after {}", text),
+        }
+        Ok(())
+    }
+
+    fn levels_summary(&self) -> String {
+        [
+            Level::Verbose,
+            Level::Debug,
+            Level::Info,
+            Level::Warning,
+            Level::Error,
+            Level::Fatal,
+        ]
+        .iter()
+        .map(|level| {
+            if self.levels[level.priority() as usize] {
+                level.short()
+            } else {
+                "-"
+            }
+        })
+        .collect()
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let details = if self.details { 10 } else { 0 };
+        let [list, details, status] = Layout::vertical([
+            Constraint::Min(1),
+            Constraint::Length(details),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+
+        self.height = list.height as usize;
+        self.scroll_into_view();
+        self.draw_list(frame, list);
+        if self.details {
+            self.draw_details(frame, details);
+        }
+        self.draw_status(frame, status);
+    }
+
+    fn draw_list(&self, frame: &mut Frame, area: Rect) {
+        let formatter = Formatter::default();
+        let lines: Vec<Line> = self
+            .visible
+            .iter()
+            .enumerate()
+            .skip(self.offset)
+            .take(area.height as usize)
+            .map(|(row, i)| {
+                let msg = &self.messages[*i];
+                let text = formatter.format(msg);
+                let first = text.lines().next().unwrap_or_default().to_owned();
+                let mut style = Style::default().fg(level_color(msg.level()));
+                if row == self.selected {
+                    style = style.add_modifier(Modifier::REVERSED);
+                }
+                Line::styled(first, style)
+            })
+            .collect();
+        frame.render_widget(Paragraph::new(lines), area);
+    }
+
+    fn draw_details(&self, frame: &mut Frame, area: Rect) {
+        let Some(msg) = self.selected_message() else {
+            return;
+        };
+        let field = |name: &str, value: String| {
+            Line::from(vec![
+                Span::styled(
+                    format!("{:>8}: ", name),
+                    Style::default().add_modifier(Modifier::BOLD),
+                ),
+                Span::raw(value),
+            ])
+        };
+        let optional =
+            |value: Option<i32>| value.map(|value| value.to_string()).unwrap_or_default();
+
+        let mut lines = vec![
+            field(
+                "time",
+                msg.date_time()
+                    .map(|date_time| date_time.to_string())
+                    .unwrap_or_default(),
+            ),
+            field(
+                "level",
+                format!("{:#} ({})", msg.level(), msg.level().priority()),
+            ),
+            field("tag", msg.tag().to_owned()),
+            field(
+                "pid/tid",
+                format!(
+                    "{}/{}",
+                    optional(msg.process_id()),
+                    optional(msg.thread_id())
+                ),
+            ),
+            field("uid", optional(msg.uid())),
+            field(
+                "buffer",
+                msg.buffer()
+                    .map(|buffer| buffer.to_string())
+                    .unwrap_or_default(),
+            ),
+        ];
+        lines.extend(msg.content().lines().map(|line| Line::raw(line.to_owned())));
+
+        let block = Block::default().borders(Borders::TOP).title("Message");
+        frame.render_widget(
+            Paragraph::new(lines)
+                .block(block)
+                .wrap(Wrap { trim: false }),
+            area,
+        );
+    }
+
+    fn draw_status(&self, frame: &mut Frame, area: Rect) {
+        if let Some((prompt, text, _)) = &self.prompt {
+            let line = format!("{}{}", prompt.label(), text);
+            let width = line.chars().count() as u16;
+            let mut spans = vec![Span::raw(line)];
+            if !self.status.is_empty() {
+                spans.push(Span::styled(
+                    format!("  {}", self.status),
+                    Style::default().fg(Color::Red),
+                ));
+            }
+            frame.render_widget(Paragraph::new(Line::from(spans)), area);
+            frame.set_cursor_position(Position::new(area.x + width, area.y));
+            return;
+        }
+
+        let position = format!(
+            "{}/{} [{}] ",
+            (self.selected + 1).min(self.visible.len()),
+            self.visible.len(),
+            self.levels_summary()
+        );
+        let line = Line::from(vec![
+            Span::styled(position, Style::default().add_modifier(Modifier::REVERSED)),
+            Span::raw(" "),
+            Span::raw(self.status.as_str()),
+        ]);
+        frame.render_widget(Paragraph::new(line), area);
+    }
+}
+
+fn level_color(level: Level) -> Color {
+    match level {
+        Level::Verbose => Color::DarkGray,
+        Level::Debug => Color::Blue,
+        Level::Info => Color::Green,
+        Level::Warning => Color::Yellow,
+        Level::Error | Level::Fatal => Color::Red,
+        Level::Unknown | Level::Default | Level::Silent => Color::Reset,
+    }
+}
+
+// Parses JSON Lines, with the `json` feature, or threadtime lines.
+struct AnyParser;
+
+impl Parser for AnyParser {
+    fn parse(&mut self, line: &str) -> Result<Message> {
+        #[cfg(feature = "json")]
+        if line.trim_start().starts_with('{') {
+            return parse::json(line);
+        }
+        parse::threadtime(line)
+    }
+}
+
+fn load<R: BufRead>(input: R) -> io::Result<Vec<Message>> {
+    MessageIterator::new(input, AnyParser).collect()
+}
+
+fn main() -> Result<()> {
+    let Some(path) = env::args().nth(1) else {
+        eprintln!("{}\n\n{}", USAGE, HELP);
+        std::process::exit(2);
+    };
+    let input = decompress::open(&path).with_context(|| format!("failed to open `{}`", path))?;
+    let mut app = App::new(load(input)?);
+
+    let mut terminal = ratatui::init();
+    let result = (|| -> Result<()> {
+        while !app.quit {
+            terminal.draw(|frame| app.draw(frame))?;
+            if let Event::Key(key) = event::read()? {
+                app.handle_key(key);
+            }
+        }
+        Ok(())
+    })();
+    ratatui::restore();
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{load, App, Prompt};
+    use logcat::message::Level;
+    use ratatui::{backend::TestBackend, crossterm::event::KeyCode, Terminal};
+
+    const DATA: &str = "\
+2017-08-01 07:30:00.000  1000  1020 I ActivityManager: Start proc 1234:com.example/u0a12
+2017-08-01 07:30:01.000  1234  1250 W Example : low memory
+2017-08-01 07:30:02.000  1234  1250 E Example : out of memory
+2017-08-01 07:30:03.000  1234  1251 D Other   : done
+not a message
+";
+
+    fn app() -> App {
+        App::new(load(DATA.as_bytes()).unwrap())
+    }
+
+    fn keys(app: &mut App, keys: &str) {
+        for c in keys.chars() {
+            let code = match c {
+                '\n' => KeyCode::Enter,
+                '\x1b' => KeyCode::Esc,
+                '\x08' => KeyCode::Backspace,
+                c => KeyCode::Char(c),
+            };
+            app.handle_key(code.into());
+        }
+    }
+
+    fn selected_content(app: &App) -> &str {
+        app.selected_message().unwrap().content()
+    }
+
+    #[test]
+    fn invalid_utf8() {
+        let data = b"08-01 07:30:00.000  1234  1250 I tag     : caf\xe9\n";
+        let messages = load(&data[..]).unwrap();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].content(), "caf\u{fffd}");
+    }
+
+    #[test]
+    fn scroll() {
+        let mut app = app();
+        assert_eq!(app.visible.len(), 4);
+        keys(&mut app, "jj");
+        assert_eq!(selected_content(&app), "out of memory");
+        keys(&mut app, "G");
+        assert_eq!(selected_content(&app), "done");
+        keys(&mut app, "kg");
+        assert_eq!(app.selected, 0);
+    }
+
+    #[test]
+    fn search() {
+        let mut app = app();
+        keys(&mut app, "/MEM");
+        assert_eq!(
+            app.prompt.as_ref().map(|prompt| prompt.0),
+            Some(Prompt::Search)
+        );
+        assert_eq!(selected_content(&app), "low memory");
+        keys(&mut app, "\n");
+        assert!(app.prompt.is_none());
+        keys(&mut app, "n");
+        assert_eq!(selected_content(&app), "out of memory");
+        keys(&mut app, "N");
+        assert_eq!(selected_content(&app), "low memory");
+
+        keys(&mut app, "G/oth\x1b");
+        assert_eq!(selected_content(&app), "done");
+    }
+
+    #[test]
+    fn filter_and_levels() {
+        let mut app = app();
+        keys(&mut app, "ftag == \"Example\"");
+        assert_eq!(app.visible.len(), 2);
+        keys(&mut app, "\x08");
+        assert_eq!(app.visible.len(), 2);
+        assert!(!app.status.is_empty());
+        keys(&mut app, "\"\n");
+        assert_eq!(app.filter_text, "tag == \"Example\"");
+
+        keys(&mut app, "5");
+        assert_eq!(app.visible.len(), 1);
+        assert_eq!(app.selected_message().unwrap().level(), Level::Error);
+        assert_eq!(app.levels_summary(), "VDI-EF");
+        keys(&mut app, "5f\x08\x1b");
+        assert_eq!(app.visible.len(), 2);
+    }
+
+    #[test]
+    fn jump_to_time() {
+        let mut app = app();
+        keys(&mut app, "t07:30:02\n");
+        assert_eq!(selected_content(&app), "out of memory");
+        keys(&mut app, "t2018-01-01 00:00\n");
+        assert!(app.prompt.is_some());
+        assert!(app.status.starts_with("no messages"));
+    }
+
+    #[test]
+    fn draw() {
+        let mut app = app();
+        keys(&mut app, "j\n");
+        let mut terminal = Terminal::new(TestBackend::new(60, 16)).unwrap();
+        terminal.draw(|frame| app.draw(frame)).unwrap();
+
+        let buffer = terminal.backend().buffer();
+        let row = |y: u16| -> String {
+            (0..buffer.area.width)
+                .map(|x| buffer[(x, y)].symbol())
+                .collect::<String>()
+                .trim_end()
+                .to_owned()
+        };
+        assert_eq!(
+            row(1),
+            "08-01 07:30:01.000  1234  1250 W Example : low memory"
+        );
+        assert!(row(5).contains("Message"));
+        assert_eq!(row(8), "     tag: Example");
+        assert!(row(15).starts_with("2/4 [VDIWEF]"));
+    }
+}
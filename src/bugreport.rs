@@ -0,0 +1,463 @@
+//! Reading the logs in Android bugreports.
+//!
+//! A bugreport, as taken by `adb bugreport`, is a zip file containing a
+//! `bugreport-*.txt` file, which is the output of `dumpstate`. The logs are
+//! in sections between headers such as:
+//!
+//! ```text
+//! ------ SYSTEM LOG (logcat -v threadtime -v printable -v uid -d *:v) ------
+//! --------- beginning of main
+//! 01-02 10:00:00.000  1000  1234  1250 I ActivityManager: Start proc ...
+//! ------ 0.512s was the duration of 'SYSTEM LOG' ------
+//! ```
+//!
+//! A [`Bugreport`] finds the [`Section`]s with logs, and parses their
+//! messages with the buffer set, from the section or from the `beginning of`
+//! lines within it, and the year of the `== dumpstate:` time, as logcat
+//! lines have none. Messages dated after the dumpstate time are from the
+//! year before it. Kernel messages are not dated, as their time since boot
+//! does not count the time the device was suspended.
+//!
+//! The device and the time of the bugreport are available as
+//! [`BugreportMetadata`].
+//!
+//! Zip files need the `zip` feature. Text files can also be compressed, as
+//! by [`decompress::open`].
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use logcat::bugreport::{Bugreport, Section};
+//!
+//! let bugreport = Bugreport::open("bugreport.zip").unwrap();
+//! for msg in bugreport.messages(Section::System) {
+//!     if msg.level().is_warning_or_higher() {
+//!         println!("{}", msg.content());
+//!     }
+//! }
+//! ```
+
+mod metadata;
+
+pub use metadata::BugreportMetadata;
+
+use crate::decompress;
+use crate::message::{Buffer, Level, Message, MessageBuilder};
+use crate::parse::{Parser, ThreadTimeParser};
+use chrono::{Datelike, Duration, NaiveDateTime};
+use std::fmt;
+use std::io::{self, Read};
+use std::ops::Range;
+use std::path::Path;
+use thiserror::Error;
+
+/// The error type for reading bugreports.
+#[derive(Debug, Error)]
+pub enum Error {
+    /// Reading the bugreport failed.
+    #[error("failed to read: {0}")]
+    Io(#[from] io::Error),
+    /// The zip file is invalid.
+    #[cfg(feature = "zip")]
+    #[error("invalid zip file: {0}")]
+    Zip(#[from] zip::result::ZipError),
+    /// The zip file does not contain a bugreport text file.
+    #[error("no bugreport found in zip file")]
+    NotFound,
+}
+
+/// A section of a bugreport containing logs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Section {
+    /// `SYSTEM LOG`, the main, system and crash buffers.
+    System,
+    /// `EVENT LOG`, the events buffer.
+    Event,
+    /// `RADIO LOG`, the radio buffer.
+    Radio,
+    /// `LAST LOGCAT`, the logs from before the last reboot.
+    LastLogcat,
+    /// `KERNEL LOG`, the output of `dmesg`.
+    Kernel,
+}
+
+impl Section {
+    /// Returns the title of this `Section` in bugreports.
+    pub fn title(self) -> &'static str {
+        match self {
+            Section::System => "SYSTEM LOG",
+            Section::Event => "EVENT LOG",
+            Section::Radio => "RADIO LOG",
+            Section::LastLogcat => "LAST LOGCAT",
+            Section::Kernel => "KERNEL LOG",
+        }
+    }
+
+    /// Returns the buffer of the messages of this `Section`, until a
+    /// `beginning of` line names another.
+    pub fn buffer(self) -> Option<Buffer> {
+        match self {
+            Section::System => Some(Buffer::Main),
+            Section::Event => Some(Buffer::Events),
+            Section::Radio => Some(Buffer::Radio),
+            Section::LastLogcat => None,
+            Section::Kernel => Some(Buffer::Kernel),
+        }
+    }
+
+    fn from_title(title: &str) -> Option<Section> {
+        [
+            Section::System,
+            Section::Event,
+            Section::Radio,
+            Section::LastLogcat,
+            Section::Kernel,
+        ]
+        .into_iter()
+        .find(|section| section.title() == title)
+    }
+}
+
+impl fmt::Display for Section {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.title())
+    }
+}
+
+/// An Android bugreport.
+#[derive(Debug)]
+pub struct Bugreport {
+    text: String,
+    metadata: BugreportMetadata,
+    sections: Vec<(Section, Range<usize>)>,
+}
+
+impl Bugreport {
+    /// Opens the bugreport zip or text file at `path`.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Bugreport, Error> {
+        let mut bytes = Vec::new();
+        decompress::open(path)?.read_to_end(&mut bytes)?;
+        Bugreport::from_bytes(bytes)
+    }
+
+    /// Reads a bugreport from the content of a zip or text file.
+    pub fn from_bytes(bytes: Vec<u8>) -> Result<Bugreport, Error> {
+        if bytes.starts_with(b"PK\x03\x04") {
+            return Bugreport::from_zip(bytes);
+        }
+        let text = String::from_utf8(bytes)
+            .unwrap_or_else(|e| String::from_utf8_lossy(e.as_bytes()).into_owned());
+        Ok(Bugreport::from_text(text))
+    }
+
+    /// Reads a bugreport from the content of its text file.
+    pub fn from_text(text: String) -> Bugreport {
+        let mut metadata = BugreportMetadata::default();
+        let mut sections = Vec::new();
+        let mut current: Option<(Section, usize)> = None;
+        let mut header = true;
+        let mut uptime = false;
+
+        let mut end = 0;
+        for line in text.split_inclusive('\n') {
+            let start = end;
+            end += line.len();
+            let line = line.trim_end();
+
+            // Sections start with a header, and end with the next one, which
+            // may be the duration of the section.
+            if let Some(title) = line
+                .strip_prefix("------ ")
+                .and_then(|title| title.strip_suffix(" ------"))
+            {
+                header = false;
+                if let Some((section, begin)) = current.take() {
+                    sections.push((section, begin..start));
+                }
+                let title = title.split(" (").next().unwrap_or(title);
+                current = Section::from_title(title).map(|section| (section, end));
+                uptime = title == "UPTIME";
+            } else if header {
+                metadata.header_line(line);
+            } else if uptime {
+                metadata.uptime_line(line);
+                uptime = false;
+            }
+        }
+        if let Some((section, begin)) = current {
+            sections.push((section, begin..text.len()));
+        }
+
+        Bugreport {
+            text,
+            metadata,
+            sections,
+        }
+    }
+
+    #[cfg(feature = "zip")]
+    fn from_zip(bytes: Vec<u8>) -> Result<Bugreport, Error> {
+        let mut archive = zip::ZipArchive::new(io::Cursor::new(bytes))?;
+
+        // `main_entry.txt` names the bugreport text file.
+        let mut name = String::new();
+        if let Ok(mut entry) = archive.by_name("main_entry.txt") {
+            entry.read_to_string(&mut name)?;
+        }
+        let name = match name.trim() {
+            "" => archive
+                .file_names()
+                .find(|name| name.starts_with("bugreport") && name.ends_with(".txt"))
+                .ok_or(Error::NotFound)?
+                .to_owned(),
+            name => name.to_owned(),
+        };
+
+        let mut bytes = Vec::new();
+        archive.by_name(&name)?.read_to_end(&mut bytes)?;
+        Bugreport::from_bytes(bytes)
+    }
+
+    #[cfg(not(feature = "zip"))]
+    fn from_zip(_bytes: Vec<u8>) -> Result<Bugreport, Error> {
+        Err(Error::Io(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "zip bugreports require the `zip` feature",
+        )))
+    }
+
+    /// Returns the information from the header of the bugreport.
+    pub fn metadata(&self) -> &BugreportMetadata {
+        &self.metadata
+    }
+
+    /// Returns the sections with logs, in the order of the bugreport.
+    pub fn sections(&self) -> Vec<Section> {
+        self.sections.iter().map(|(section, _)| *section).collect()
+    }
+
+    /// Returns the text of `section`, or `None` if the bugreport does not
+    /// have it.
+    pub fn text(&self, section: Section) -> Option<&str> {
+        self.sections
+            .iter()
+            .find(|(found, _)| *found == section)
+            .map(|(_, range)| &self.text[range.clone()])
+    }
+
+    /// Returns the messages of `section`, which are none if the bugreport
+    /// does not have it.
+    pub fn messages(&self, section: Section) -> Messages<'_> {
+        let dumpstate = self.metadata.dumpstate_time();
+        let mut parser = ThreadTimeParser::new();
+        if let Some(dumpstate) = dumpstate {
+            parser.year(dumpstate.year());
+        }
+        Messages {
+            lines: self.text(section).unwrap_or_default().lines(),
+            section,
+            buffer: section.buffer(),
+            parser,
+            dumpstate,
+        }
+    }
+}
+
+/// An iterator over the messages of a section of a bugreport.
+///
+/// Created by [`Bugreport::messages`].
+pub struct Messages<'a> {
+    lines: std::str::Lines<'a>,
+    section: Section,
+    buffer: Option<Buffer>,
+    parser: ThreadTimeParser,
+    dumpstate: Option<NaiveDateTime>,
+}
+
+impl Messages<'_> {
+    fn complete(&self, msg: Message) -> Message {
+        let mut builder = MessageBuilder::from(&msg);
+        if let Some(buffer) = self.buffer {
+            builder.buffer(buffer);
+        }
+        if let (Some(date_time), Some(dumpstate)) = (msg.date_time(), self.dumpstate) {
+            // Allow for the clock of the logs being ahead.
+            if date_time > dumpstate + Duration::days(1) {
+                // February 29 becomes February 28 in a year that is not leap.
+                let year = date_time.year() - 1;
+                if let Some(date_time) = date_time
+                    .with_year(year)
+                    .or_else(|| (date_time - Duration::days(1)).with_year(year))
+                {
+                    builder.date_time(date_time);
+                }
+            }
+        }
+        builder.build().expect("all required fields are set")
+    }
+}
+
+impl Iterator for Messages<'_> {
+    type Item = Message;
+
+    fn next(&mut self) -> Option<Message> {
+        loop {
+            let line = self.lines.next()?;
+            if let Some(name) = line
+                .strip_prefix("--------- beginning of ")
+                .or_else(|| line.strip_prefix("--------- switch to "))
+            {
+                self.buffer = name.trim().parse().ok();
+                continue;
+            }
+
+            let msg = match self.section {
+                Section::Kernel => kernel(line),
+                _ => self.parser.parse(line).ok(),
+            };
+            if let Some(msg) = msg {
+                return Some(self.complete(msg));
+            }
+        }
+    }
+}
+
+// Parses a `dmesg` line, such as `<6>[   12.345678] init: started`. The
+// seconds since boot are dropped, as they stop while the device is suspended.
+fn kernel(line: &str) -> Option<Message> {
+    let (level, rest) = match line.strip_prefix('<') {
+        Some(rest) => {
+            let (priority, rest) = rest.split_once('>')?;
+            // The priority may be combined with a facility, as in `<14>`.
+            let level = match priority.parse::<u32>().ok()? & 7 {
+                0..=2 => Level::Fatal,
+                3 => Level::Error,
+                4 => Level::Warning,
+                5 | 6 => Level::Info,
+                _ => Level::Debug,
+            };
+            (level, rest)
+        }
+        None => (Level::Info, line),
+    };
+    let (_, content) = rest.trim_start().strip_prefix('[')?.split_once(']')?;
+    MessageBuilder::new()
+        .level(level)
+        .tag("kernel")
+        .content(content.strip_prefix(' ').unwrap_or(content))
+        .build()
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bugreport::{Bugreport, Section};
+    use crate::message::{Buffer, Level};
+    use chrono::Datelike;
+
+    const BUGREPORT: &str = "\
+========================================================
+== dumpstate: 2024-01-02 10:22:33
+========================================================
+
+Build: UP1A.231005.007
+Build fingerprint: 'google/raven/raven:14/UP1A.231005.007/10754064:user/release-keys'
+Bootloader: slider-1.2-9152140
+
+------ UPTIME (uptime) ------
+ 10:22:33 up 2 days,  3:04,  0 users,  load average: 1.00, 1.00, 1.00
+------ 0.002s was the duration of 'UPTIME' ------
+------ SYSTEM LOG (logcat -v threadtime -v printable -v uid -d *:v) ------
+--------- beginning of main
+02-29 08:00:00.000  1000  1234  1250 I ActivityManager: Leap day
+12-31 23:59:59.000  1000  1234  1250 I ActivityManager: Start proc 1234:com.example/u0a12
+--------- beginning of system
+01-02 10:00:00.000  1000  1234  1250 W ActivityManager: Slow operation
+------ 0.512s was the duration of 'SYSTEM LOG' ------
+------ EVENT LOG (logcat -b events -v threadtime -v printable -v uid -d *:v) ------
+01-02 10:00:00.000  1000  1234  1250 I am_proc_start: [0,1234,10012,com.example]
+------ KERNEL LOG (dmesg) ------
+<3>[   12.345678] init: failed
+[   13.000000] binder: started
+<14>[   14.000000] logd: started
+";
+
+    #[test]
+    fn bugreport() {
+        let bugreport = Bugreport::from_bytes(BUGREPORT.as_bytes().to_vec()).unwrap();
+        let metadata = bugreport.metadata();
+        assert_eq!(
+            metadata.dumpstate_time().unwrap().to_string(),
+            "2024-01-02 10:22:33"
+        );
+        assert_eq!(metadata.build(), Some("UP1A.231005.007"));
+        assert_eq!(metadata.device(), Some("raven"));
+        assert_eq!(metadata.bootloader(), Some("slider-1.2-9152140"));
+        assert_eq!(
+            metadata.boot_time().unwrap().to_string(),
+            "2023-12-31 07:18:33"
+        );
+        assert_eq!(
+            bugreport.sections(),
+            [Section::System, Section::Event, Section::Kernel]
+        );
+        assert!(bugreport.text(Section::Radio).is_none());
+        assert_eq!(bugreport.messages(Section::Radio).count(), 0);
+
+        let messages: Vec<_> = bugreport.messages(Section::System).collect();
+        assert_eq!(messages.len(), 3);
+        assert_eq!(
+            messages[0].date_time().unwrap().to_string(),
+            "2023-02-28 08:00:00"
+        );
+        assert_eq!(messages[1].buffer(), Some(Buffer::Main));
+        assert_eq!(messages[1].uid(), Some(1000));
+        assert_eq!(messages[1].date().unwrap().year(), 2023);
+        assert_eq!(messages[2].buffer(), Some(Buffer::System));
+        assert_eq!(messages[2].date().unwrap().year(), 2024);
+        assert_eq!(messages[2].content(), "Slow operation");
+
+        let messages: Vec<_> = bugreport.messages(Section::Event).collect();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].tag(), "am_proc_start");
+        assert_eq!(messages[0].buffer(), Some(Buffer::Events));
+
+        let messages: Vec<_> = bugreport.messages(Section::Kernel).collect();
+        assert_eq!(messages.len(), 3);
+        assert_eq!(messages[0].level(), Level::Error);
+        assert_eq!(messages[0].content(), "init: failed");
+        assert_eq!(messages[0].buffer(), Some(Buffer::Kernel));
+        assert_eq!(messages[0].date_time(), None);
+        assert_eq!(messages[1].level(), Level::Info);
+        assert_eq!(messages[2].level(), Level::Info);
+    }
+
+    #[cfg(feature = "zip")]
+    #[test]
+    fn bugreport_zip() {
+        use std::io::Write;
+        use zip::write::{SimpleFileOptions, ZipWriter};
+
+        let mut writer = ZipWriter::new(std::io::Cursor::new(Vec::new()));
+        let options = SimpleFileOptions::default();
+        writer.start_file("main_entry.txt", options).unwrap();
+        writer.write_all(b"bugreport-raven-UP1A.txt").unwrap();
+        writer.start_file("version.txt", options).unwrap();
+        writer.write_all(b"2.0").unwrap();
+        writer
+            .start_file("bugreport-raven-UP1A.txt", options)
+            .unwrap();
+        writer.write_all(BUGREPORT.as_bytes()).unwrap();
+        let bytes = writer.finish().unwrap().into_inner();
+
+        let bugreport = Bugreport::from_bytes(bytes).unwrap();
+        assert_eq!(bugreport.messages(Section::System).count(), 3);
+    }
+
+    #[cfg(not(feature = "zip"))]
+    #[test]
+    fn bugreport_zip_unsupported() {
+        let e = Bugreport::from_bytes(b"PK\x03\x04".to_vec()).unwrap_err();
+        assert!(e.to_string().contains("`zip` feature"));
+    }
+}
@@ -0,0 +1,183 @@
+use chrono::{Duration, NaiveDateTime};
+
+/// Information about the device and the time of a bugreport, from its
+/// header.
+///
+/// The header of a bugreport looks like:
+///
+/// ```text
+/// ========================================================
+/// == dumpstate: 2024-01-02 10:22:33
+/// ========================================================
+///
+/// Build: UP1A.231005.007
+/// Build fingerprint: 'google/raven/raven:14/UP1A.231005.007/10754064:user/release-keys'
+/// Bootloader: slider-1.2-9152140
+/// Uptime: up 0 weeks, 2 days, 3 hours, 4 minutes
+/// ```
+///
+/// The uptime is also read from the `UPTIME` section, for bugreports without
+/// it in the header.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BugreportMetadata {
+    dumpstate_time: Option<NaiveDateTime>,
+    build: Option<String>,
+    build_fingerprint: Option<String>,
+    bootloader: Option<String>,
+    uptime: Option<Duration>,
+}
+
+impl BugreportMetadata {
+    /// Returns the time of the `== dumpstate:` line, when the bugreport was
+    /// taken, in the time zone of the device.
+    pub fn dumpstate_time(&self) -> Option<NaiveDateTime> {
+        self.dumpstate_time
+    }
+
+    /// Returns the build ID, such as `UP1A.231005.007`.
+    pub fn build(&self) -> Option<&str> {
+        self.build.as_deref()
+    }
+
+    /// Returns the build fingerprint, such as
+    /// `google/raven/raven:14/UP1A.231005.007/10754064:user/release-keys`.
+    pub fn build_fingerprint(&self) -> Option<&str> {
+        self.build_fingerprint.as_deref()
+    }
+
+    /// Returns the device name from the build fingerprint, such as `raven`.
+    pub fn device(&self) -> Option<&str> {
+        let fingerprint = self.build_fingerprint.as_deref()?;
+        let (device, _) = fingerprint.split('/').nth(2)?.split_once(':')?;
+        Some(device)
+    }
+
+    /// Returns the bootloader version.
+    pub fn bootloader(&self) -> Option<&str> {
+        self.bootloader.as_deref()
+    }
+
+    /// Returns the time since the device booted, to the minute.
+    pub fn uptime(&self) -> Option<Duration> {
+        self.uptime
+    }
+
+    /// Returns the time the device booted, to the minute, from the dumpstate
+    /// time and the uptime.
+    pub fn boot_time(&self) -> Option<NaiveDateTime> {
+        Some(self.dumpstate_time? - self.uptime?)
+    }
+
+    // Reads a line of the header, before the first section.
+    pub(super) fn header_line(&mut self, line: &str) {
+        let Some((key, value)) = line.split_once(':') else {
+            return;
+        };
+        let value = value.trim();
+        match key.trim() {
+            "== dumpstate" => {
+                self.dumpstate_time = NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S").ok()
+            }
+            "Build" => self.build = Some(value.to_owned()),
+            "Build fingerprint" => {
+                self.build_fingerprint = Some(value.trim_matches('\'').to_owned())
+            }
+            "Bootloader" => self.bootloader = Some(value.to_owned()),
+            "Uptime" => self.uptime = parse_uptime(value),
+            _ => {}
+        }
+    }
+
+    // Reads the first line of the `UPTIME` section.
+    pub(super) fn uptime_line(&mut self, line: &str) {
+        if self.uptime.is_none() {
+            self.uptime = parse_uptime(line);
+        }
+    }
+}
+
+// Parses an uptime, as printed by `uptime` in `10:22:33 up 2 days,  3:04,
+// 0 users, ...`, or by dumpstate in `up 0 weeks, 2 days, 3 hours, 4 minutes`.
+fn parse_uptime(text: &str) -> Option<Duration> {
+    let (_, rest) = text.split_once("up ")?;
+    let mut uptime = Duration::zero();
+    let mut found = false;
+    for part in rest.split(',').map(str::trim) {
+        if let Some((hours, minutes)) = part.split_once(':') {
+            uptime +=
+                Duration::hours(hours.parse().ok()?) + Duration::minutes(minutes.parse().ok()?);
+        } else {
+            let (count, unit) = part.split_once(' ')?;
+            let Ok(count) = count.parse() else {
+                break;
+            };
+            uptime += match unit.trim_end_matches('s') {
+                "week" => Duration::weeks(count),
+                "day" => Duration::days(count),
+                "hour" => Duration::hours(count),
+                "min" | "minute" => Duration::minutes(count),
+                // Such as `0 users`, after the uptime.
+                _ => break,
+            };
+        }
+        found = true;
+    }
+    found.then_some(uptime)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bugreport::metadata::{parse_uptime, BugreportMetadata};
+    use chrono::Duration;
+
+    #[test]
+    fn metadata() {
+        let mut metadata = BugreportMetadata::default();
+        for line in [
+            "== dumpstate: 2024-01-02 10:22:33",
+            "Build: UP1A.231005.007",
+            "Build fingerprint: 'google/raven/raven:14/UP1A.231005.007/10754064:user/release-keys'",
+            "Bootloader: slider-1.2-9152140",
+            "Network: (unknown)",
+            "Uptime: up 0 weeks, 0 days, 2 hours, 30 minutes",
+        ] {
+            metadata.header_line(line);
+        }
+        metadata.uptime_line(" 10:22:33 up 5 min,  0 users");
+
+        assert_eq!(metadata.build(), Some("UP1A.231005.007"));
+        assert_eq!(
+            metadata.build_fingerprint(),
+            Some("google/raven/raven:14/UP1A.231005.007/10754064:user/release-keys")
+        );
+        assert_eq!(metadata.device(), Some("raven"));
+        assert_eq!(metadata.bootloader(), Some("slider-1.2-9152140"));
+        assert_eq!(metadata.uptime(), Some(Duration::minutes(150)));
+        assert_eq!(
+            metadata.boot_time().unwrap().to_string(),
+            "2024-01-02 07:52:33"
+        );
+    }
+
+    #[test]
+    fn uptime() {
+        let cases = [
+            (
+                " 10:22:33 up 2 days,  3:04,  0 users,  load average: 1.00",
+                2 * 1440 + 184,
+            ),
+            (" 10:22:33 up 1 day, 10 min,  0 users", 1440 + 10),
+            (" 10:22:33 up  3:04,  0 users", 184),
+            ("up 1 week, 0 days, 1 hour, 1 minute", 7 * 1440 + 61),
+        ];
+        for (text, minutes) in cases {
+            assert_eq!(
+                parse_uptime(text),
+                Some(Duration::minutes(minutes)),
+                "{}",
+                text
+            );
+        }
+        assert_eq!(parse_uptime("unknown"), None);
+    }
+}
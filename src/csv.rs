@@ -0,0 +1,377 @@
+//! Writing messages as CSV or TSV, for use in spreadsheets.
+//!
+//! # Examples
+//!
+//! ```
+//! use logcat::{
+//!     csv::{Column, Writer},
+//!     parse,
+//! };
+//!
+//! let line = "08-01 07:30:00.123  1234  1250 W tag     : one, two";
+//! let msg = parse::threadtime(line).unwrap();
+//!
+//! let mut writer = Writer::new(Vec::new());
+//! writer.columns(&[Column::Level, Column::Tag, Column::Content]);
+//! writer.write(&msg).unwrap();
+//!
+//! let output = String::from_utf8(writer.into_inner()).unwrap();
+//! assert_eq!(output, "level,tag,content\nW,tag,\"one, two\"\n");
+//! ```
+
+use crate::analyze::ProcessTracker;
+use crate::message::Message;
+use std::borrow::Cow;
+use std::fmt;
+use std::io::{self, Write};
+use std::str::FromStr;
+use thiserror::Error;
+
+/// The error type for parsing a [`Column`].
+#[derive(Debug, Error, Eq, PartialEq)]
+pub enum Error {
+    /// The specified column name is not known.
+    #[error("invalid column: `{0}`")]
+    InvalidColumn(String),
+}
+
+/// Columns that can be written.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Column {
+    /// Date and time, such as `2017-08-01 07:30:00.123`, to the precision of
+    /// the message.
+    Time,
+    /// Short description of the level, such as `W`.
+    Level,
+    Pid,
+    Tid,
+    Uid,
+    Tag,
+    Content,
+    Buffer,
+    /// Name of the process, as known from earlier written messages.
+    ///
+    /// See [`ProcessTracker`].
+    ProcessName,
+}
+
+impl Column {
+    /// All columns, in the default order.
+    pub const ALL: [Column; 9] = [
+        Column::Time,
+        Column::Level,
+        Column::Pid,
+        Column::Tid,
+        Column::Uid,
+        Column::Tag,
+        Column::Content,
+        Column::Buffer,
+        Column::ProcessName,
+    ];
+
+    /// Returns the name of this `Column`, as used in the header row.
+    pub fn name(self) -> &'static str {
+        match self {
+            Column::Time => "time",
+            Column::Level => "level",
+            Column::Pid => "pid",
+            Column::Tid => "tid",
+            Column::Uid => "uid",
+            Column::Tag => "tag",
+            Column::Content => "content",
+            Column::Buffer => "buffer",
+            Column::ProcessName => "process",
+        }
+    }
+}
+
+impl fmt::Display for Column {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(self.name())
+    }
+}
+
+impl FromStr for Column {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Column, Error> {
+        let column = match s.to_ascii_lowercase().as_str() {
+            "time" => Column::Time,
+            "level" => Column::Level,
+            "pid" => Column::Pid,
+            "tid" => Column::Tid,
+            "uid" => Column::Uid,
+            "tag" => Column::Tag,
+            "content" | "message" => Column::Content,
+            "buffer" => Column::Buffer,
+            "process" | "process_name" => Column::ProcessName,
+            _ => return Err(Error::InvalidColumn(s.to_owned())),
+        };
+        Ok(column)
+    }
+}
+
+/// Writes messages as delimited rows, one row per message.
+///
+/// Fields containing the delimiter, quotes or line breaks are quoted, so
+/// multi-line content stays in one cell. Missing fields are left empty.
+///
+/// Fields starting with `=`, `+`, `-` or `@` are prefixed with `'` by
+/// default, so that spreadsheets do not run them as formulas. See
+/// [`Writer::sanitize_formulas`].
+pub struct Writer<W> {
+    inner: W,
+    columns: Vec<Column>,
+    delimiter: u8,
+    header: bool,
+    sanitize_formulas: bool,
+    started: bool,
+    tracker: ProcessTracker,
+}
+
+impl<W: Write> Writer<W> {
+    /// Creates a new CSV `Writer` with all columns and a header row.
+    pub fn new(inner: W) -> Writer<W> {
+        Writer {
+            inner,
+            columns: Column::ALL.to_vec(),
+            delimiter: b',',
+            header: true,
+            sanitize_formulas: true,
+            started: false,
+            tracker: ProcessTracker::new(),
+        }
+    }
+
+    /// Creates a new TSV `Writer` with all columns and a header row.
+    pub fn tsv(inner: W) -> Writer<W> {
+        let mut writer = Writer::new(inner);
+        writer.delimiter(b'\t');
+        writer
+    }
+
+    /// Sets the columns to write, in order.
+    pub fn columns(&mut self, columns: &[Column]) -> &mut Self {
+        self.columns = columns.to_vec();
+        self
+    }
+
+    /// Sets the field delimiter, such as `b','` or `b'\t'`.
+    pub fn delimiter(&mut self, delimiter: u8) -> &mut Self {
+        self.delimiter = delimiter;
+        self
+    }
+
+    /// Sets whether to write a header row with the column names.
+    pub fn header(&mut self, value: bool) -> &mut Self {
+        self.header = value;
+        self
+    }
+
+    /// Sets whether to prefix fields that a spreadsheet would read as a
+    /// formula with `'`. Enabled by default.
+    pub fn sanitize_formulas(&mut self, value: bool) -> &mut Self {
+        self.sanitize_formulas = value;
+        self
+    }
+
+    /// Writes one message as a row, preceded by the header row if this is
+    /// the first message.
+    pub fn write(&mut self, msg: &Message) -> io::Result<()> {
+        self.start()?;
+        self.tracker.track(msg);
+
+        let fields: Vec<_> = self
+            .columns
+            .iter()
+            .map(|column| field(&self.tracker, msg, *column))
+            .collect();
+        self.write_row(fields.iter().map(String::as_str))
+    }
+
+    /// Learns process names from a message that is not written, such as one
+    /// that is filtered out.
+    pub fn track(&mut self, msg: &Message) {
+        self.tracker.track(msg);
+    }
+
+    /// Writes all `messages`, and the header row even if there are none.
+    pub fn write_all<I: IntoIterator<Item = Message>>(&mut self, messages: I) -> io::Result<()> {
+        self.start()?;
+        for msg in messages {
+            self.write(&msg)?;
+        }
+        Ok(())
+    }
+
+    /// Flushes the underlying writer.
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+
+    /// Returns the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+
+    fn start(&mut self) -> io::Result<()> {
+        if self.started {
+            return Ok(());
+        }
+        self.started = true;
+        if self.header {
+            let names: Vec<_> = self.columns.iter().map(|column| column.name()).collect();
+            self.write_row(names.into_iter())?;
+        }
+        Ok(())
+    }
+
+    fn write_row<'a>(&mut self, fields: impl Iterator<Item = &'a str>) -> io::Result<()> {
+        let mut row = Vec::new();
+        for (i, field) in fields.enumerate() {
+            if i > 0 {
+                row.push(self.delimiter);
+            }
+            let field = if self.sanitize_formulas && field.starts_with(['=', '+', '-', '@']) {
+                Cow::Owned(format!("'{}", field))
+            } else {
+                Cow::Borrowed(field)
+            };
+            let needs_quotes = field
+                .bytes()
+                .any(|b| b == self.delimiter || b == b'"' || b == b'\n' || b == b'\r');
+            if needs_quotes {
+                row.push(b'"');
+                row.extend_from_slice(field.replace('"', "\"\"").as_bytes());
+                row.push(b'"');
+            } else {
+                row.extend_from_slice(field.as_bytes());
+            }
+        }
+        row.push(b'\n');
+        self.inner.write_all(&row)
+    }
+}
+
+fn field(tracker: &ProcessTracker, msg: &Message, column: Column) -> String {
+    fn optional<T: ToString>(value: Option<T>) -> String {
+        value.map(|value| value.to_string()).unwrap_or_default()
+    }
+
+    match column {
+        Column::Time => optional(
+            msg.date_time()
+                .map(|date_time| date_time.format("%Y-%m-%d %H:%M:%S%.f")),
+        ),
+        Column::Level => msg.level().short().to_owned(),
+        Column::Pid => optional(msg.process_id()),
+        Column::Tid => optional(msg.thread_id()),
+        Column::Uid => optional(msg.uid()),
+        Column::Tag => msg.tag().to_owned(),
+        Column::Content => msg.content().to_owned(),
+        Column::Buffer => optional(msg.buffer()),
+        Column::ProcessName => optional(tracker.process_name_for(msg)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::csv::{Column, Error, Writer};
+    use crate::message::{Buffer, Level, MessageBuilder};
+    use crate::parse;
+
+    #[test]
+    fn csv() {
+        let lines = [
+            "2017-08-01 07:30:00.123  1000  1020 I ActivityManager: Start proc 1234:com.example/u0a12 for activity com.example/.Main",
+            "2017-08-01 07:30:01.456789  1234  1250 W Example : one, \"two\"",
+        ];
+        let mut messages: Vec<_> = lines
+            .iter()
+            .map(|line| parse::threadtime(line).unwrap())
+            .collect();
+        messages.push(
+            MessageBuilder::new()
+                .level(Level::Error)
+                .tag("tag")
+                .content("line 1\nline 2")
+                .buffer(Buffer::Crash)
+                .build()
+                .unwrap(),
+        );
+
+        let mut writer = Writer::new(Vec::new());
+        writer.track(&messages[0]);
+        writer.write_all(messages.iter().skip(1).cloned()).unwrap();
+        let output = String::from_utf8(writer.into_inner()).unwrap();
+        assert_eq!(
+            output,
+            "time,level,pid,tid,uid,tag,content,buffer,process\n\
+             2017-08-01 07:30:01.456789,W,1234,1250,,Example,\"one, \"\"two\"\"\",,com.example\n\
+             ,E,,,,tag,\"line 1\nline 2\",crash,\n"
+        );
+
+        let mut writer = Writer::tsv(Vec::new());
+        writer
+            .columns(&[Column::ProcessName, Column::Pid, Column::Content])
+            .header(false);
+        for msg in &messages[..2] {
+            writer.write(msg).unwrap();
+        }
+        let output = String::from_utf8(writer.into_inner()).unwrap();
+        assert_eq!(
+            output,
+            "\t1000\tStart proc 1234:com.example/u0a12 for activity com.example/.Main\n\
+             com.example\t1234\t\"one, \"\"two\"\"\"\n"
+        );
+    }
+
+    #[test]
+    fn csv_header_only() {
+        let mut writer = Writer::new(Vec::new());
+        writer.columns(&[Column::Tag, Column::Level]);
+        writer.write_all(Vec::new()).unwrap();
+        assert_eq!(writer.into_inner(), b"tag,level\n");
+    }
+
+    #[test]
+    fn csv_formulas() {
+        let msg = MessageBuilder::new()
+            .level(Level::Info)
+            .tag("@tag")
+            .content("=HYPERLINK(\"http://example.com\", \"a,b\")")
+            .build()
+            .unwrap();
+
+        let mut writer = Writer::new(Vec::new());
+        writer
+            .columns(&[Column::Level, Column::Tag, Column::Content])
+            .header(false);
+        writer.write(&msg).unwrap();
+        let output = String::from_utf8(writer.into_inner()).unwrap();
+        assert_eq!(
+            output,
+            "I,'@tag,\"'=HYPERLINK(\"\"http://example.com\"\", \"\"a,b\"\")\"\n"
+        );
+
+        let mut writer = Writer::tsv(Vec::new());
+        writer
+            .columns(&[Column::Tag])
+            .header(false)
+            .sanitize_formulas(false);
+        writer.write(&msg).unwrap();
+        assert_eq!(writer.into_inner(), b"@tag\n");
+    }
+
+    #[test]
+    fn column() {
+        for column in &Column::ALL {
+            assert_eq!(column.name().parse(), Ok(*column));
+        }
+        assert_eq!("message".parse(), Ok(Column::Content));
+        assert_eq!(
+            "date".parse::<Column>(),
+            Err(Error::InvalidColumn("date".to_owned()))
+        );
+    }
+}
@@ -0,0 +1,180 @@
+//! Transparent decompression of compressed logs.
+//!
+//! The compression of an input is detected from its first bytes, so that
+//! compressed and plain logs can be read the same way. Each format needs a
+//! cargo feature:
+//!
+//! | Format | Feature | Magic bytes         |
+//! |--------|---------|---------------------|
+//! | gzip   | `gzip`  | `1f 8b`             |
+//! | zstd   | `zstd`  | `28 b5 2f fd`       |
+//! | xz     | `xz`    | `fd 37 7a 58 5a 00` |
+//!
+//! Reading a compressed input without its feature fails with
+//! [`io::ErrorKind::Unsupported`], rather than producing garbled messages.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use logcat::{decompress, parse::{MessageIterator, ThreadTimeParser}};
+//!
+//! let input = decompress::open("logcat.txt.gz").unwrap();
+//! for msg in MessageIterator::new(input, ThreadTimeParser::new()) {
+//!     println!("{}", msg.unwrap().content());
+//! }
+//! ```
+
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Cursor, Read};
+use std::path::Path;
+
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+const XZ_MAGIC: &[u8] = &[0xfd, 0x37, 0x7a, 0x58, 0x5a, 0x00];
+
+/// A compression format.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Compression {
+    /// Not compressed.
+    None,
+    Gzip,
+    Zstd,
+    Xz,
+}
+
+impl Compression {
+    /// Returns the compression of an input starting with `bytes`.
+    pub fn detect(bytes: &[u8]) -> Compression {
+        if bytes.starts_with(GZIP_MAGIC) {
+            Compression::Gzip
+        } else if bytes.starts_with(ZSTD_MAGIC) {
+            Compression::Zstd
+        } else if bytes.starts_with(XZ_MAGIC) {
+            Compression::Xz
+        } else {
+            Compression::None
+        }
+    }
+
+    // Returns the cargo feature needed to decompress this format.
+    fn feature(self) -> &'static str {
+        match self {
+            Compression::None => "",
+            Compression::Gzip => "gzip",
+            Compression::Zstd => "zstd",
+            Compression::Xz => "xz",
+        }
+    }
+}
+
+/// Opens the file at `path`, decompressing it if needed.
+pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Box<dyn BufRead + Send>> {
+    reader(File::open(path)?)
+}
+
+/// Returns a reader of `input`, decompressing it if needed.
+pub fn reader<'a, R: Read + Send + 'a>(mut input: R) -> io::Result<Box<dyn BufRead + Send + 'a>> {
+    // Read the magic bytes, and put them back in front of the rest.
+    let mut magic = Vec::with_capacity(XZ_MAGIC.len());
+    (&mut input)
+        .take(XZ_MAGIC.len() as u64)
+        .read_to_end(&mut magic)?;
+    let compression = Compression::detect(&magic);
+    let input = Cursor::new(magic).chain(input);
+
+    match compression {
+        Compression::None => Ok(Box::new(BufReader::new(input))),
+        #[cfg(feature = "gzip")]
+        Compression::Gzip => Ok(Box::new(BufReader::new(flate2::read::MultiGzDecoder::new(
+            input,
+        )))),
+        #[cfg(feature = "zstd")]
+        Compression::Zstd => Ok(Box::new(BufReader::new(zstd::Decoder::new(input)?))),
+        #[cfg(feature = "xz")]
+        Compression::Xz => Ok(Box::new(BufReader::new(
+            xz2::read::XzDecoder::new_multi_decoder(input),
+        ))),
+        #[allow(unreachable_patterns)]
+        compression => Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            format!(
+                "{:?} compressed input requires the `{}` feature",
+                compression,
+                compression.feature()
+            ),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::decompress::{self, Compression};
+    use std::io::{self, Read};
+
+    const DATA: &str = "08-01 07:30:00.123  1234  1250 W tag     : content\n";
+
+    fn decompress(input: &[u8]) -> io::Result<String> {
+        let mut output = String::new();
+        decompress::reader(input)?.read_to_string(&mut output)?;
+        Ok(output)
+    }
+
+    #[test]
+    fn plain() {
+        assert_eq!(decompress(DATA.as_bytes()).unwrap(), DATA);
+        assert_eq!(decompress(b"x").unwrap(), "x");
+        assert_eq!(decompress(b"").unwrap(), "");
+    }
+
+    #[test]
+    fn detect() {
+        assert_eq!(Compression::detect(b"\x1f\x8b\x08"), Compression::Gzip);
+        assert_eq!(Compression::detect(b"\x28\xb5\x2f\xfd"), Compression::Zstd);
+        assert_eq!(
+            Compression::detect(b"\xfd\x37\x7a\x58\x5a\x00"),
+            Compression::Xz
+        );
+        assert_eq!(Compression::detect(b"\xfd\x37"), Compression::None);
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn gzip() {
+        use flate2::{write::GzEncoder, Compression};
+        use std::io::Write;
+
+        // Concatenated members, as written by `cat a.gz b.gz`.
+        let mut input = Vec::new();
+        for _ in 0..2 {
+            let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(DATA.as_bytes()).unwrap();
+            input.extend(encoder.finish().unwrap());
+        }
+        assert_eq!(decompress(&input).unwrap(), DATA.repeat(2));
+    }
+
+    #[cfg(not(feature = "gzip"))]
+    #[test]
+    fn gzip_unsupported() {
+        let e = decompress(b"\x1f\x8b\x08\x00").unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::Unsupported);
+        assert!(e.to_string().contains("`gzip` feature"));
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn zstd() {
+        let input = zstd::encode_all(DATA.as_bytes(), 0).unwrap();
+        assert_eq!(decompress(&input).unwrap(), DATA);
+    }
+
+    #[cfg(feature = "xz")]
+    #[test]
+    fn xz() {
+        let mut input = Vec::new();
+        xz2::read::XzEncoder::new(DATA.as_bytes(), 6)
+            .read_to_end(&mut input)
+            .unwrap();
+        assert_eq!(decompress(&input).unwrap(), DATA);
+    }
+}
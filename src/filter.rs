@@ -0,0 +1,253 @@
+//! Filtering of messages with logcat filterspecs.
+//!
+//! A filterspec is a list of `tag:priority` expressions, such as
+//! `ActivityManager:I MyApp:D *:S`, as accepted by `adb logcat` and the
+//! `ANDROID_LOG_TAGS` environment variable, read by [`Filter::from_env`].
+//!
+//! # Examples
+//!
+//! ```
+//! use logcat::{filter::Filter, parse};
+//!
+//! let filter: Filter = "ActivityManager:I MyApp:D *:S".parse().unwrap();
+//!
+//! let source = "...";
+//! for line in source.lines() {
+//!     if let Ok(msg) = parse::threadtime(line) {
+//!         if filter.matches(&msg) {
+//!             println!("{}", msg.content());
+//!         }
+//!     }
+//! }
+//! ```
+
+use crate::message::{Level, Message};
+use std::env;
+use std::str::FromStr;
+use thiserror::Error;
+
+/// The error type for parsing a [`Filter`].
+#[derive(Debug, Error, Eq, PartialEq)]
+pub enum Error {
+    /// The expression has no tag, such as `:D`.
+    #[error("missing tag: `{0}`")]
+    MissingTag(String),
+
+    /// The expression has an unknown priority, such as `Tag:X`.
+    #[error("invalid priority: `{0}`")]
+    InvalidPriority(String),
+}
+
+/// Parses the priority of an expression, such as `D` or `3`.
+fn parse_priority(c: char) -> Option<Level> {
+    match c {
+        '*' => Some(Level::Default),
+        // logcat treats priorities above `Silent` as `Verbose`, and `8` as
+        // one of them.
+        '8' | '9' => Some(Level::Verbose),
+        '1'..='7' => Level::try_from(c as u8 - b'0').ok(),
+        c if c.is_ascii_digit() => None,
+        c => Level::try_from(c)
+            .ok()
+            .filter(|&level| level != Level::Unknown),
+    }
+}
+
+/// A logcat filterspec.
+///
+/// Each expression sets the minimum priority for a tag. The `*` tag sets the
+/// priority of tags without an expression, and is `Verbose` unless set. As
+/// in logcat, a missing priority or `*` means `Verbose` for a tag and `Debug`
+/// for `*`, `S` (`Silent`) hides all messages, and a later expression for a
+/// tag overrides an earlier one.
+///
+/// Unlike logcat, which shows messages logged at the `Silent` priority even
+/// under an `S` expression, `S` also hides those.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Filter {
+    rules: Vec<(String, Level)>,
+    global: Level,
+}
+
+impl Default for Filter {
+    fn default() -> Filter {
+        Filter {
+            rules: Vec::new(),
+            global: Level::Verbose,
+        }
+    }
+}
+
+impl Filter {
+    /// Creates a new Filter that matches all messages.
+    pub fn new() -> Filter {
+        Filter::default()
+    }
+
+    /// Parses the filterspec in the `ANDROID_LOG_TAGS` environment variable.
+    ///
+    /// Returns a filter that matches all messages if the variable is not set.
+    pub fn from_env() -> Result<Filter, Error> {
+        match env::var("ANDROID_LOG_TAGS") {
+            Ok(spec) => Filter::parse(&spec),
+            Err(_) => Ok(Filter::new()),
+        }
+    }
+
+    /// Parses a filterspec of expressions separated by whitespace or commas.
+    pub fn parse(spec: &str) -> Result<Filter, Error> {
+        let mut filter = Filter::new();
+        for expression in spec
+            .split(|c: char| c.is_whitespace() || c == ',')
+            .filter(|expression| !expression.is_empty())
+        {
+            filter.add(expression)?;
+        }
+        Ok(filter)
+    }
+
+    /// Adds a `tag:priority` expression.
+    pub fn add(&mut self, expression: &str) -> Result<&mut Self, Error> {
+        let (tag, priority) = match expression.split_once(':') {
+            Some((tag, priority)) => {
+                let priority = priority
+                    .chars()
+                    .next()
+                    .and_then(parse_priority)
+                    .ok_or_else(|| Error::InvalidPriority(expression.to_owned()))?;
+                (tag, Some(priority))
+            }
+            None => (expression, None),
+        };
+
+        match (tag, priority) {
+            ("", _) => return Err(Error::MissingTag(expression.to_owned())),
+            ("*", None | Some(Level::Default)) => self.global = Level::Debug,
+            ("*", Some(priority)) => self.global = priority,
+            (tag, priority) => {
+                // As in logcat, `Tag:*` means `Verbose` for a tag.
+                let priority = match priority {
+                    None | Some(Level::Default) => Level::Verbose,
+                    Some(priority) => priority,
+                };
+                self.rules.push((tag.to_owned(), priority));
+            }
+        }
+        Ok(self)
+    }
+
+    /// Returns `true` if a message with `tag` and `level` passes the filter.
+    pub fn is_visible(&self, tag: &str, level: Level) -> bool {
+        let priority = self
+            .rules
+            .iter()
+            .rev()
+            .find(|(rule, _)| rule == tag)
+            .map_or(self.global, |&(_, priority)| priority);
+        priority != Level::Silent && level >= priority
+    }
+
+    /// Returns `true` if `message` passes the filter.
+    pub fn matches(&self, message: &Message) -> bool {
+        self.is_visible(message.tag(), message.level())
+    }
+}
+
+impl FromStr for Filter {
+    type Err = Error;
+
+    fn from_str(spec: &str) -> Result<Filter, Error> {
+        Filter::parse(spec)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        filter::{Error, Filter},
+        message::Level,
+    };
+
+    #[test]
+    fn filter_default() {
+        let filter = Filter::new();
+        assert!(filter.is_visible("tag", Level::Verbose));
+
+        let filter = Filter::parse("").unwrap();
+        assert!(filter.is_visible("tag", Level::Verbose));
+    }
+
+    #[test]
+    fn filter_tags() {
+        let filter = Filter::parse("ActivityManager:I MyApp:D *:S").unwrap();
+
+        assert!(!filter.is_visible("ActivityManager", Level::Debug));
+        assert!(filter.is_visible("ActivityManager", Level::Info));
+        assert!(filter.is_visible("ActivityManager", Level::Fatal));
+        assert!(!filter.is_visible("MyApp", Level::Verbose));
+        assert!(filter.is_visible("MyApp", Level::Debug));
+        assert!(!filter.is_visible("Other", Level::Fatal));
+    }
+
+    #[test]
+    fn filter_defaults() {
+        // `*` defaults to Debug and tags default to Verbose.
+        let filter = Filter::parse("* MyApp").unwrap();
+        assert!(!filter.is_visible("Other", Level::Verbose));
+        assert!(filter.is_visible("Other", Level::Debug));
+        assert!(filter.is_visible("MyApp", Level::Verbose));
+
+        // `Tag:*` is `Verbose`, whatever the priority of `*`.
+        let filter = Filter::parse("MyApp:* *:W").unwrap();
+        assert!(filter.is_visible("MyApp", Level::Verbose));
+        assert!(filter.is_visible("MyApp", Level::Info));
+        assert!(!filter.is_visible("Other", Level::Info));
+    }
+
+    #[test]
+    fn filter_override() {
+        let filter = Filter::parse("MyApp:E,MyApp:V *:S").unwrap();
+        assert!(filter.is_visible("MyApp", Level::Verbose));
+
+        let filter = Filter::parse("MyApp:s").unwrap();
+        assert!(!filter.is_visible("MyApp", Level::Fatal));
+        assert!(!filter.is_visible("MyApp", Level::Silent));
+        assert!(filter.is_visible("Other", Level::Verbose));
+    }
+
+    #[test]
+    fn filter_numeric() {
+        let filter = Filter::parse("MyApp:5 *:8").unwrap();
+        assert!(!filter.is_visible("MyApp", Level::Info));
+        assert!(filter.is_visible("MyApp", Level::Warning));
+        assert!(filter.is_visible("Other", Level::Verbose));
+    }
+
+    #[test]
+    fn filter_env() {
+        std::env::set_var("ANDROID_LOG_TAGS", "MyApp:W *:S");
+        let filter = Filter::from_env().unwrap();
+        std::env::remove_var("ANDROID_LOG_TAGS");
+        assert!(filter.is_visible("MyApp", Level::Warning));
+        assert!(!filter.is_visible("Other", Level::Fatal));
+
+        assert_eq!(Filter::from_env(), Ok(Filter::new()));
+    }
+
+    #[test]
+    fn filter_malformed() {
+        assert_eq!(Filter::parse(":D"), Err(Error::MissingTag(":D".to_owned())));
+        assert_eq!(
+            Filter::parse("MyApp:X"),
+            Err(Error::InvalidPriority("MyApp:X".to_owned()))
+        );
+        assert_eq!(
+            Filter::parse("MyApp:"),
+            Err(Error::InvalidPriority("MyApp:".to_owned()))
+        );
+        assert_eq!(
+            Filter::parse("MyApp:0"),
+            Err(Error::InvalidPriority("MyApp:0".to_owned()))
+        );
+    }
+}
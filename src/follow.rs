@@ -0,0 +1,342 @@
+//! Following a growing log file, as `tail -F` does.
+//!
+//! A [`Follower`] reads the lines appended to a file since it last looked,
+//! and parses the complete ones with a [`Parser`]. Lines that fail to parse,
+//! such as `--------- beginning of main`, are skipped.
+//!
+//! A file that becomes shorter is considered truncated, and is read again
+//! from the start. A file that is replaced, such as when `logcat -f` rotates
+//! `logcat` to `logcat.1`, is read to its end before the new file is opened.
+//! Rotation is detected from the file identity on Unix, and only as a
+//! truncation elsewhere.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use logcat::{follow::Follower, parse::ThreadTimeParser};
+//!
+//! let follower = Follower::new("/tmp/logcat", ThreadTimeParser::new());
+//! for msg in follower {
+//!     let msg = msg.unwrap();
+//!     if msg.level().is_warning_or_higher() {
+//!         println!("{}", msg.content());
+//!     }
+//! }
+//! ```
+
+use crate::message::Message;
+use crate::parse::Parser;
+use std::collections::VecDeque;
+use std::fs::{self, File, Metadata};
+use std::io::{self, Read, Seek, SeekFrom};
+use std::ops::ControlFlow;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
+
+// The most bytes read at once.
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Follows a file, parsing the lines appended to it.
+pub struct Follower<P> {
+    path: PathBuf,
+    parser: P,
+    interval: Duration,
+    from_end: bool,
+    file: Option<File>,
+    identity: Option<(u64, u64)>,
+    position: u64,
+    // The start of a line whose end has not been written yet.
+    partial: Vec<u8>,
+    // Whether to skip to the next line, as when starting from the end.
+    skip_line: bool,
+    pending: VecDeque<Message>,
+}
+
+impl<P: Parser> Follower<P> {
+    /// Creates a new `Follower` for the file at `path`, which does not need
+    /// to exist yet, reading it from the start and polling every 250ms.
+    pub fn new<T: AsRef<Path>>(path: T, parser: P) -> Follower<P> {
+        Follower {
+            path: path.as_ref().to_owned(),
+            parser,
+            interval: Duration::from_millis(250),
+            from_end: false,
+            file: None,
+            identity: None,
+            position: 0,
+            partial: Vec::new(),
+            skip_line: false,
+            pending: VecDeque::new(),
+        }
+    }
+
+    /// Sets the time to wait between polls when there is nothing new.
+    pub fn interval(&mut self, value: Duration) -> &mut Self {
+        self.interval = value;
+        self
+    }
+
+    /// Sets whether to skip the content present when the file is first
+    /// opened, and only read what is appended afterwards.
+    pub fn from_end(&mut self, value: bool) -> &mut Self {
+        self.from_end = value;
+        self
+    }
+
+    /// Returns the messages appended since the last poll, without waiting.
+    pub fn poll(&mut self) -> io::Result<Vec<Message>> {
+        self.read()?;
+        Ok(self.pending.drain(..).collect())
+    }
+
+    /// Calls `callback` with each message as it is appended, until the
+    /// callback returns [`ControlFlow::Break`] or reading fails.
+    pub fn follow<F>(&mut self, mut callback: F) -> io::Result<()>
+    where
+        F: FnMut(Message) -> ControlFlow<()>,
+    {
+        loop {
+            self.read()?;
+            while let Some(msg) = self.pending.pop_front() {
+                if callback(msg).is_break() {
+                    return Ok(());
+                }
+            }
+            thread::sleep(self.interval);
+        }
+    }
+
+    fn read(&mut self) -> io::Result<()> {
+        let metadata = match fs::metadata(&self.path) {
+            Ok(metadata) => Some(metadata),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+            Err(e) => return Err(e),
+        };
+
+        if self.file.is_some() {
+            let replaced = metadata
+                .as_ref()
+                .is_some_and(|metadata| identity(metadata) != self.identity);
+            if replaced {
+                // Finish the rotated file, whose last line is complete.
+                self.read_appended()?;
+                let rest = std::mem::take(&mut self.partial);
+                self.parse_line(&rest);
+                self.file = None;
+            } else if metadata
+                .as_ref()
+                .is_some_and(|metadata| metadata.len() < self.position)
+            {
+                self.restart()?;
+            }
+        }
+
+        if self.file.is_none() {
+            let Some(metadata) = metadata else {
+                return Ok(());
+            };
+            let mut file = File::open(&self.path)?;
+            self.identity = identity(&metadata);
+            self.position = 0;
+            if self.from_end {
+                self.position = file.seek(SeekFrom::End(0))?;
+                if self.position > 0 {
+                    // Skip the rest of a line that is still being written.
+                    let mut last = [0];
+                    file.seek(SeekFrom::End(-1))?;
+                    file.read_exact(&mut last)?;
+                    self.skip_line = last[0] != b'\n';
+                }
+                self.from_end = false;
+            }
+            self.file = Some(file);
+        }
+        self.read_appended()
+    }
+
+    fn restart(&mut self) -> io::Result<()> {
+        if let Some(file) = &mut self.file {
+            file.seek(SeekFrom::Start(0))?;
+        }
+        self.position = 0;
+        self.partial.clear();
+        self.skip_line = false;
+        Ok(())
+    }
+
+    fn read_appended(&mut self) -> io::Result<()> {
+        let mut chunk = [0; CHUNK_SIZE];
+        loop {
+            let Some(file) = &mut self.file else {
+                return Ok(());
+            };
+            let read = match file.read(&mut chunk) {
+                Ok(0) => return Ok(()),
+                Ok(read) => read,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            self.position += read as u64;
+            self.parse_chunk(&chunk[..read]);
+        }
+    }
+
+    fn parse_chunk(&mut self, mut chunk: &[u8]) {
+        if self.skip_line {
+            let Some(end) = chunk.iter().position(|b| *b == b'\n') else {
+                return;
+            };
+            chunk = &chunk[end + 1..];
+            self.skip_line = false;
+        }
+        let start = self.partial.len();
+        self.partial.extend_from_slice(chunk);
+
+        // Only search the new bytes for line ends.
+        let Some(end) = self.partial[start..].iter().rposition(|b| *b == b'\n') else {
+            return;
+        };
+        let rest = self.partial.split_off(start + end + 1);
+        let lines = std::mem::replace(&mut self.partial, rest);
+        for line in lines.split(|b| *b == b'\n') {
+            self.parse_line(line);
+        }
+    }
+
+    fn parse_line(&mut self, line: &[u8]) {
+        let line = String::from_utf8_lossy(line);
+        let line = line.strip_suffix('\r').unwrap_or(&line);
+        if line.is_empty() {
+            return;
+        }
+        if let Ok(msg) = self.parser.parse(line) {
+            self.pending.push_back(msg);
+        }
+    }
+}
+
+/// Blocks until the next message is appended.
+impl<P: Parser> Iterator for Follower<P> {
+    type Item = io::Result<Message>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(msg) = self.pending.pop_front() {
+                return Some(Ok(msg));
+            }
+            if let Err(e) = self.read() {
+                return Some(Err(e));
+            }
+            if self.pending.is_empty() {
+                thread::sleep(self.interval);
+            }
+        }
+    }
+}
+
+#[cfg(unix)]
+fn identity(metadata: &Metadata) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+    Some((metadata.dev(), metadata.ino()))
+}
+
+#[cfg(not(unix))]
+fn identity(_metadata: &Metadata) -> Option<(u64, u64)> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::follow::Follower;
+    use crate::parse::ThreadTimeParser;
+    use std::fs::{self, OpenOptions};
+    use std::io::Write;
+    use std::ops::ControlFlow;
+    use std::time::Duration;
+
+    fn append(path: &std::path::Path, data: &str) {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .unwrap();
+        file.write_all(data.as_bytes()).unwrap();
+    }
+
+    fn line(content: &str) -> String {
+        format!("08-01 07:30:00.000  1234  1250 I tag: {}\n", content)
+    }
+
+    #[test]
+    fn follow() {
+        let dir = std::env::temp_dir().join(format!("logcat-follow-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("logcat");
+        let _ = fs::remove_file(&path);
+
+        let mut follower = Follower::new(&path, ThreadTimeParser::new());
+        let mut poll = || -> Vec<String> {
+            follower
+                .poll()
+                .unwrap()
+                .iter()
+                .map(|msg| msg.content().to_owned())
+                .collect()
+        };
+        assert!(poll().is_empty());
+
+        // Complete lines only.
+        append(&path, "--------- beginning of main\n");
+        append(&path, &line("one"));
+        append(&path, "08-01 07:30:00.000  1234  1250 I tag: tw");
+        assert_eq!(poll(), ["one"]);
+        append(&path, "o\r\n");
+        append(&path, &line("three"));
+        assert_eq!(poll(), ["two", "three"]);
+        assert!(poll().is_empty());
+
+        // Truncation.
+        fs::write(&path, line("four")).unwrap();
+        assert_eq!(poll(), ["four"]);
+
+        // Rotation, with a line written just before it.
+        append(&path, &line("five"));
+        fs::rename(&path, dir.join("logcat.1")).unwrap();
+        append(&path, &line("six"));
+        assert_eq!(poll(), ["five", "six"]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn follow_callback() {
+        let dir = std::env::temp_dir().join(format!("logcat-callback-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("logcat");
+        fs::write(
+            &path,
+            line("skipped") + "08-01 07:30:00.000  1234  1250 I tag: par",
+        )
+        .unwrap();
+
+        let mut follower = Follower::new(&path, ThreadTimeParser::new());
+        follower.from_end(true).interval(Duration::from_millis(1));
+        assert!(follower.poll().unwrap().is_empty());
+        append(&path, "tial\n");
+        append(&path, &line("one"));
+        append(&path, &line("two"));
+
+        let mut contents = Vec::new();
+        follower
+            .follow(|msg| {
+                contents.push(msg.content().to_owned());
+                ControlFlow::Break(())
+            })
+            .unwrap();
+        assert_eq!(contents, ["one"]);
+        assert_eq!(follower.next().unwrap().unwrap().content(), "two");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
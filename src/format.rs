@@ -0,0 +1,401 @@
+//! Rendering of messages in logcat text formats.
+//!
+//! The output of a [`Formatter`] matches `adb logcat -v <format>`, so that
+//! parsed messages can be written back out after filtering or redaction.
+//!
+//! # Examples
+//!
+//! ```
+//! use logcat::{
+//!     format::{Format, Formatter},
+//!     parse,
+//! };
+//!
+//! let line = "12-31 22:59:41.271     1   197 I init    : Uptime: 00002.612275";
+//! let msg = parse::threadtime(line).unwrap();
+//!
+//! let formatter = Formatter::new(Format::ThreadTime);
+//! assert_eq!(formatter.format(&msg), format!("{}\n", line));
+//!
+//! let formatter = Formatter::new(Format::Brief);
+//! assert_eq!(formatter.format(&msg), "I/init    (    1): Uptime: 00002.612275\n");
+//! ```
+
+use crate::message::Message;
+use chrono::{FixedOffset, Timelike};
+use std::fmt::{self, Write as _};
+use std::io;
+use std::str::FromStr;
+use thiserror::Error;
+
+/// The error type for parsing a [`Format`].
+#[derive(Debug, Error, Eq, PartialEq)]
+pub enum Error {
+    /// The specified format name is not known.
+    #[error("invalid format: `{0}`")]
+    InvalidFormat(String),
+}
+
+/// Logcat output formats, as selected with `logcat -v`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Format {
+    /// `I/tag     ( 1234): content`
+    Brief,
+    /// `I( 1234) content  (tag)`
+    Process,
+    /// `I/tag     : content`
+    Tag,
+    /// `I( 1234: 1250) content`
+    Thread,
+    /// `content`
+    Raw,
+    /// `12-31 22:59:41.271 I/tag     ( 1234): content`
+    Time,
+    /// `12-31 22:59:41.271  1234  1250 I tag     : content`
+    ThreadTime,
+    /// `[ 12-31 22:59:41.271  1234: 1250 I/tag      ]`, followed by the
+    /// content and an empty line.
+    Long,
+}
+
+impl Format {
+    /// Returns the name of this `Format`, as used by `logcat -v`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Format::Brief => "brief",
+            Format::Process => "process",
+            Format::Tag => "tag",
+            Format::Thread => "thread",
+            Format::Raw => "raw",
+            Format::Time => "time",
+            Format::ThreadTime => "threadtime",
+            Format::Long => "long",
+        }
+    }
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(self.name())
+    }
+}
+
+impl FromStr for Format {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Format, Error> {
+        let format = match s {
+            "brief" => Format::Brief,
+            "process" => Format::Process,
+            "tag" => Format::Tag,
+            "thread" => Format::Thread,
+            "raw" => Format::Raw,
+            "time" => Format::Time,
+            "threadtime" => Format::ThreadTime,
+            "long" => Format::Long,
+            _ => return Err(Error::InvalidFormat(s.to_owned())),
+        };
+        Ok(format)
+    }
+}
+
+/// Renders messages in a logcat [`Format`].
+///
+/// Missing fields are rendered as zero, or as the Unix epoch for timestamps.
+/// Messages with multiple lines of content are rendered as one line per line
+/// of content, as by logcat.
+#[derive(Clone, Debug)]
+pub struct Formatter {
+    format: Format,
+    year: bool,
+    usec: bool,
+    nsec: bool,
+    zone: Option<FixedOffset>,
+    uid: bool,
+}
+
+impl Formatter {
+    /// Creates a new Formatter for `format` without modifiers.
+    pub fn new(format: Format) -> Formatter {
+        Formatter {
+            format,
+            year: false,
+            usec: false,
+            nsec: false,
+            zone: None,
+            uid: false,
+        }
+    }
+
+    /// Sets whether to include the year in timestamps (the `year` modifier).
+    pub fn year(&mut self, value: bool) -> &mut Self {
+        self.year = value;
+        self
+    }
+
+    /// Sets whether to render timestamps with microseconds (the `usec`
+    /// modifier).
+    pub fn usec(&mut self, value: bool) -> &mut Self {
+        self.usec = value;
+        self
+    }
+
+    /// Sets whether to render timestamps with nanoseconds (the `nsec`
+    /// modifier). Takes precedence over [`usec`](Formatter::usec).
+    pub fn nsec(&mut self, value: bool) -> &mut Self {
+        self.nsec = value;
+        self
+    }
+
+    /// Sets the time zone offset to append to timestamps (the `zone`
+    /// modifier).
+    ///
+    /// Timestamps of messages are not converted, as they are already in the
+    /// time zone of the device.
+    pub fn zone(&mut self, value: Option<FixedOffset>) -> &mut Self {
+        self.zone = value;
+        self
+    }
+
+    /// Sets whether to include the user ID (the `uid` modifier).
+    pub fn uid(&mut self, value: bool) -> &mut Self {
+        self.uid = value;
+        self
+    }
+
+    /// Returns the rendered `message`, including the trailing newline.
+    pub fn format(&self, message: &Message) -> String {
+        let mut out = String::new();
+        self.render(&mut out, message)
+            .expect("writing to a String cannot fail");
+        out
+    }
+
+    /// Writes the rendered `message`, including the trailing newline.
+    pub fn write<W: io::Write>(&self, out: &mut W, message: &Message) -> io::Result<()> {
+        out.write_all(self.format(message).as_bytes())
+    }
+
+    fn render(&self, out: &mut String, msg: &Message) -> fmt::Result {
+        let level = msg.level().short();
+        let tag = msg.tag();
+        let pid = msg.process_id().unwrap_or(0);
+        let tid = msg.thread_id().unwrap_or(0);
+        let time = self.timestamp(msg);
+        let uid = match (self.uid, msg.uid()) {
+            (false, _) => String::new(),
+            (true, Some(uid)) => format!("{:5}:", uid),
+            (true, None) => "      ".to_owned(),
+        };
+
+        if self.format == Format::Long {
+            writeln!(
+                out,
+                "[ {} {}{:5}:{:5} {}/{:<8} ]",
+                time, uid, pid, tid, level, tag
+            )?;
+            writeln!(out, "{}", msg.content())?;
+            return writeln!(out);
+        }
+
+        for line in msg.content().split('\n') {
+            match self.format {
+                Format::Brief => {
+                    writeln!(out, "{}/{:<8}({}{:5}): {}", level, tag, uid, pid, line)
+                }
+                Format::Process => {
+                    writeln!(out, "{}({}{:5}) {}  ({})", level, uid, pid, line, tag)
+                }
+                Format::Tag => writeln!(out, "{}/{:<8}: {}", level, tag, line),
+                Format::Thread => {
+                    writeln!(out, "{}({}{:5}:{:5}) {}", level, uid, pid, tid, line)
+                }
+                Format::Raw => writeln!(out, "{}", line),
+                Format::Time => writeln!(
+                    out,
+                    "{} {}/{:<8}({}{:5}): {}",
+                    time, level, tag, uid, pid, line
+                ),
+                Format::ThreadTime => {
+                    // The uid is separated by a space instead of a colon.
+                    let uid = uid.replacen(':', " ", 1);
+                    writeln!(
+                        out,
+                        "{} {}{:5} {:5} {} {:<8}: {}",
+                        time, uid, pid, tid, level, tag, line
+                    )
+                }
+                Format::Long => unreachable!(),
+            }?;
+        }
+        Ok(())
+    }
+
+    fn timestamp(&self, msg: &Message) -> String {
+        let date_time = msg.date_time().unwrap_or_default();
+        let mut timestamp = if self.year {
+            date_time.format("%Y-%m-%d %H:%M:%S").to_string()
+        } else {
+            date_time.format("%m-%d %H:%M:%S").to_string()
+        };
+
+        // Leap seconds are represented with more than 1,000,000,000ns.
+        let nanos = date_time.nanosecond() % 1_000_000_000;
+        if self.nsec {
+            let _ = write!(timestamp, ".{:09}", nanos);
+        } else if self.usec {
+            let _ = write!(timestamp, ".{:06}", nanos / 1_000);
+        } else {
+            let _ = write!(timestamp, ".{:03}", nanos / 1_000_000);
+        }
+
+        if let Some(zone) = self.zone {
+            let _ = write!(timestamp, " {}", zone_name(zone));
+        }
+        timestamp
+    }
+}
+
+impl Default for Formatter {
+    fn default() -> Formatter {
+        Formatter::new(Format::ThreadTime)
+    }
+}
+
+// +0100
+fn zone_name(zone: FixedOffset) -> String {
+    let seconds = zone.local_minus_utc();
+    let sign = if seconds < 0 { '-' } else { '+' };
+    let minutes = seconds.abs() / 60;
+    format!("{}{:02}{:02}", sign, minutes / 60, minutes % 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        format::{Error, Format, Formatter},
+        message::{Level, MessageBuilder},
+        parse,
+    };
+    use chrono::{FixedOffset, NaiveDate};
+
+    fn message() -> crate::message::Message {
+        MessageBuilder::new()
+            .level(Level::Warning)
+            .tag("Tag")
+            .content("first\nsecond")
+            .date_time(
+                NaiveDate::from_ymd_opt(2017, 8, 1)
+                    .unwrap()
+                    .and_hms_micro_opt(7, 30, 0, 123_456)
+                    .unwrap(),
+            )
+            .process_id(1234)
+            .thread_id(1250)
+            .uid(10012)
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn formats() {
+        let msg = message();
+        let cases = [
+            (
+                Format::Brief,
+                "W/Tag     ( 1234): first\nW/Tag     ( 1234): second\n",
+            ),
+            (
+                Format::Process,
+                "W( 1234) first  (Tag)\nW( 1234) second  (Tag)\n",
+            ),
+            (Format::Tag, "W/Tag     : first\nW/Tag     : second\n"),
+            (
+                Format::Thread,
+                "W( 1234: 1250) first\nW( 1234: 1250) second\n",
+            ),
+            (Format::Raw, "first\nsecond\n"),
+            (
+                Format::Time,
+                "08-01 07:30:00.123 W/Tag     ( 1234): first\n\
+                 08-01 07:30:00.123 W/Tag     ( 1234): second\n",
+            ),
+            (
+                Format::ThreadTime,
+                "08-01 07:30:00.123  1234  1250 W Tag     : first\n\
+                 08-01 07:30:00.123  1234  1250 W Tag     : second\n",
+            ),
+            (
+                Format::Long,
+                "[ 08-01 07:30:00.123  1234: 1250 W/Tag      ]\nfirst\nsecond\n\n",
+            ),
+        ];
+
+        for (format, expected) in &cases {
+            assert_eq!(Formatter::new(*format).format(&msg), *expected);
+            assert_eq!(format.name().parse(), Ok(*format));
+        }
+        assert_eq!(
+            "json".parse::<Format>(),
+            Err(Error::InvalidFormat("json".to_owned()))
+        );
+    }
+
+    #[test]
+    fn modifiers() {
+        let msg = message();
+        let zone = FixedOffset::east_opt(-(5 * 3600 + 30 * 60)).unwrap();
+
+        let mut formatter = Formatter::new(Format::ThreadTime);
+        formatter.year(true).usec(true).zone(Some(zone)).uid(true);
+        assert!(formatter
+            .format(&msg)
+            .starts_with("2017-08-01 07:30:00.123456 -0530 10012  1234  1250 W Tag     : first\n"));
+
+        let mut formatter = Formatter::new(Format::Brief);
+        formatter.uid(true);
+        assert!(formatter
+            .format(&msg)
+            .starts_with("W/Tag     (10012: 1234): first\n"));
+
+        let mut formatter = Formatter::new(Format::Time);
+        formatter.nsec(true);
+        assert!(formatter
+            .format(&msg)
+            .starts_with("08-01 07:30:00.123456000 W/Tag"));
+    }
+
+    #[test]
+    fn round_trip() {
+        let cases = [
+            (
+                Formatter::new(Format::ThreadTime),
+                "12-31 22:59:41.271     1   197 I init    : Uptime: 00002.612275",
+            ),
+            (
+                Formatter::new(Format::ThreadTime),
+                "01-01 00:00:00.000 12345 12346 V a_longer_tag: ",
+            ),
+            (
+                Formatter::new(Format::ThreadTime)
+                    .year(true)
+                    .usec(true)
+                    .uid(true)
+                    .clone(),
+                "2017-08-01 07:30:00.123456 10012  1234  1250 E Tag     :   padded content ",
+            ),
+            (
+                Formatter::new(Format::ThreadTime)
+                    .nsec(true)
+                    .zone(FixedOffset::east_opt(3600))
+                    .clone(),
+                "08-01 07:30:00.123456789 +0100  1234  1250 F Tag     : content",
+            ),
+        ];
+
+        for (formatter, line) in &cases {
+            let msg = parse::threadtime(line).unwrap();
+            assert_eq!(formatter.format(&msg), format!("{}\n", line));
+        }
+    }
+}
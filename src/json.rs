@@ -0,0 +1,191 @@
+//! Reading and writing messages as JSON Lines.
+//!
+//! Each message is one JSON object on its own line, as consumed by `jq` or
+//! the Elasticsearch bulk API. Objects have the following fields:
+//!
+//! | Field       | Type    | Example                     |
+//! |-------------|---------|-----------------------------|
+//! | `level`     | string  | `"W"`                       |
+//! | `tag`       | string  | `"ActivityManager"`         |
+//! | `content`   | string  | `"Start proc 1234:com.foo"` |
+//! | `date_time` | string  | `"2017-08-01T07:30:00.123"` |
+//! | `pid`       | integer | `1234`                      |
+//! | `tid`       | integer | `1250`                      |
+//! | `uid`       | integer | `10012`                     |
+//! | `buffer`    | string  | `"main"`                    |
+//!
+//! `level`, `tag` and `content` are always present, the other fields are
+//! omitted when not known. Levels are written as their short description,
+//! and read from short or long descriptions or priorities. Date and time
+//! are in ISO 8601 format without a time zone.
+//!
+//! # Examples
+//!
+//! ```
+//! use logcat::{json, parse};
+//!
+//! let line = "08-01 07:30:00.123  1234  1250 W tag     : content";
+//! let msg = parse::threadtime(line).unwrap();
+//!
+//! let mut writer = json::Writer::new(Vec::new());
+//! writer.write(&msg).unwrap();
+//! let output = writer.into_inner();
+//!
+//! let messages: Vec<_> = json::Reader::new(&output[..])
+//!     .collect::<Result<_, _>>()
+//!     .unwrap();
+//! assert_eq!(messages, [msg]);
+//! ```
+
+use crate::message::Message;
+use std::io::{self, BufRead, Write};
+use thiserror::Error;
+
+/// The error type for reading JSON Lines.
+#[derive(Debug, Error)]
+pub enum Error {
+    /// Reading from the source failed.
+    #[error("failed to read: {0}")]
+    Io(#[from] io::Error),
+    /// A line is not a valid message object.
+    #[error("invalid message on line {line}: {source}")]
+    Json {
+        line: usize,
+        #[source]
+        source: serde_json::Error,
+    },
+}
+
+/// Writes messages as JSON Lines.
+pub struct Writer<W> {
+    inner: W,
+}
+
+impl<W: Write> Writer<W> {
+    /// Creates a new `Writer` writing to `inner`.
+    pub fn new(inner: W) -> Writer<W> {
+        Writer { inner }
+    }
+
+    /// Writes one message followed by a newline.
+    pub fn write(&mut self, msg: &Message) -> io::Result<()> {
+        serde_json::to_writer(&mut self.inner, msg)?;
+        self.inner.write_all(b"\n")
+    }
+
+    /// Flushes the underlying writer.
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+
+    /// Returns the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+/// Reads messages from JSON Lines, skipping empty lines.
+pub struct Reader<R> {
+    inner: R,
+    line: usize,
+    buf: String,
+}
+
+impl<R: BufRead> Reader<R> {
+    /// Creates a new `Reader` reading from `inner`.
+    pub fn new(inner: R) -> Reader<R> {
+        Reader {
+            inner,
+            line: 0,
+            buf: String::new(),
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for Reader<R> {
+    type Item = Result<Message, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            self.buf.clear();
+            match self.inner.read_line(&mut self.buf) {
+                Ok(0) => return None,
+                Ok(_) => self.line += 1,
+                Err(e) => return Some(Err(e.into())),
+            }
+
+            let line = self.buf.trim();
+            if line.is_empty() {
+                continue;
+            }
+            return Some(serde_json::from_str(line).map_err(|source| Error::Json {
+                line: self.line,
+                source,
+            }));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::json::{Error, Reader, Writer};
+    use crate::message::{Buffer, Level, MessageBuilder};
+
+    #[test]
+    fn json_lines() {
+        let messages = vec![
+            MessageBuilder::new()
+                .level(Level::Info)
+                .tag("init")
+                .content("Uptime: 00002.612275")
+                .process_id(1)
+                .thread_id(197)
+                .buffer(Buffer::Main)
+                .build()
+                .unwrap(),
+            MessageBuilder::new()
+                .level(Level::Error)
+                .tag("tag")
+                .content("line 1\nline \"2\"")
+                .build()
+                .unwrap(),
+        ];
+
+        let mut writer = Writer::new(Vec::new());
+        for msg in &messages {
+            writer.write(msg).unwrap();
+        }
+        let output = String::from_utf8(writer.into_inner()).unwrap();
+        assert_eq!(
+            output,
+            concat!(
+                r#"{"level":"I","tag":"init","content":"Uptime: 00002.612275","pid":1,"tid":197,"buffer":"main"}"#,
+                "\n",
+                r#"{"level":"E","tag":"tag","content":"line 1\nline \"2\""}"#,
+                "\n",
+            )
+        );
+
+        let input = output.replace('\n', "\n\n");
+        let read: Vec<_> = Reader::new(input.as_bytes())
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(read, messages);
+    }
+
+    #[test]
+    fn json_lines_error() {
+        let input = r#"{"level":"Info","tag":"a","content":"b"}
+{"level":5,"tag":"a","content":"b"}
+{"level":"Info","tag":"a"}
+"#;
+        let mut reader = Reader::new(input.as_bytes());
+        assert_eq!(reader.next().unwrap().unwrap().level(), Level::Info);
+        assert_eq!(reader.next().unwrap().unwrap().level(), Level::Warning);
+        assert!(matches!(
+            reader.next(),
+            Some(Err(Error::Json { line: 3, .. }))
+        ));
+        assert!(reader.next().is_none());
+    }
+}
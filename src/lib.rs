@@ -30,5 +30,31 @@
 //! }
 //! ```
 
+pub mod analyze;
+#[cfg(feature = "arrow")]
+pub mod arrow;
+pub mod bugreport;
+pub mod csv;
+pub mod decompress;
+pub mod filter;
+pub mod follow;
+pub mod format;
+#[cfg(feature = "json")]
+pub mod json;
+#[cfg(feature = "log")]
+pub mod logger;
 pub mod message;
+#[cfg(feature = "json")]
+pub mod otel;
 pub mod parse;
+pub mod pretty;
+pub mod query;
+pub mod rotate;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+#[cfg(feature = "tracing")]
+pub mod subscriber;
+#[cfg(any(feature = "log", feature = "tracing"))]
+mod sys;
+#[cfg(feature = "json")]
+pub mod trace;
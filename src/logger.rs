@@ -0,0 +1,166 @@
+//! A [`log`] backend writing logcat formatted lines.
+//!
+//! Records are converted into messages with the target as tag, such as
+//! `my_crate.net` for `my_crate::net`, and the current process and thread
+//! IDs, and written with a [`Formatter`], so that host-side output can be
+//! parsed like device output.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use logcat::logger::Logger;
+//!
+//! Logger::new(std::io::stderr()).init().unwrap();
+//! log::warn!("disk almost full");
+//! ```
+
+use crate::format::Formatter;
+use crate::message::{Level, Message, MessageBuilder};
+use crate::sys;
+use chrono::Local;
+use log::{LevelFilter, Log, Metadata, Record, SetLoggerError};
+use std::io::Write;
+use std::sync::Mutex;
+
+impl From<log::Level> for Level {
+    fn from(level: log::Level) -> Level {
+        match level {
+            log::Level::Error => Level::Error,
+            log::Level::Warn => Level::Warning,
+            log::Level::Info => Level::Info,
+            log::Level::Debug => Level::Debug,
+            log::Level::Trace => Level::Verbose,
+        }
+    }
+}
+
+/// Writes [`log`] records as logcat messages to a writer.
+pub struct Logger<W> {
+    out: Mutex<W>,
+    formatter: Formatter,
+    level: LevelFilter,
+}
+
+impl<W: Write + Send> Logger<W> {
+    /// Creates a new `Logger` writing `threadtime` lines to `out`, for
+    /// records of all levels.
+    pub fn new(out: W) -> Logger<W> {
+        Logger {
+            out: Mutex::new(out),
+            formatter: Formatter::default(),
+            level: LevelFilter::Trace,
+        }
+    }
+
+    /// Sets the formatter used to render messages.
+    pub fn formatter(&mut self, value: Formatter) -> &mut Self {
+        self.formatter = value;
+        self
+    }
+
+    /// Sets the most verbose level of records that are written.
+    pub fn level(&mut self, value: LevelFilter) -> &mut Self {
+        self.level = value;
+        self
+    }
+
+    /// Returns the message for `record`.
+    pub fn message(&self, record: &Record) -> Message {
+        MessageBuilder::new()
+            .level(record.level().into())
+            .tag(&sys::tag(record.target()))
+            .content(&record.args().to_string())
+            .date_time(Local::now().naive_local())
+            .process_id(sys::process_id())
+            .thread_id(sys::thread_id())
+            .build()
+            .expect("all required fields are set")
+    }
+
+    /// Returns the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.out.into_inner().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl<W: Write + Send + 'static> Logger<W> {
+    /// Sets this `Logger` as the global logger, and its level as the maximum
+    /// level of the `log` crate.
+    pub fn init(self) -> Result<(), SetLoggerError> {
+        log::set_max_level(self.level);
+        log::set_boxed_logger(Box::new(self))
+    }
+}
+
+impl<W: Write + Send> Log for Logger<W> {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.level
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let line = self.formatter.format(&self.message(record));
+        if let Ok(mut out) = self.out.lock() {
+            // There is nowhere to report a failure to log.
+            let _ = out.write_all(line.as_bytes());
+        }
+    }
+
+    fn flush(&self) {
+        if let Ok(mut out) = self.out.lock() {
+            let _ = out.flush();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::logger::Logger;
+    use crate::message::Level;
+    use crate::parse;
+    use log::{LevelFilter, Log, Record};
+
+    #[test]
+    fn logger() {
+        let mut logger = Logger::new(Vec::new());
+        logger.level(LevelFilter::Info);
+
+        let records = [
+            (log::Level::Warn, "storage", "disk almost full"),
+            (log::Level::Debug, "storage", "not written"),
+            (log::Level::Error, "storage", "disk full"),
+            (log::Level::Info, module_path!(), "from a module"),
+        ];
+        for (level, target, content) in records {
+            logger.log(
+                &Record::builder()
+                    .level(level)
+                    .target(target)
+                    .args(format_args!("{}", content))
+                    .build(),
+            );
+        }
+
+        let output = String::from_utf8(logger.into_inner()).unwrap();
+        let messages: Vec<_> = output
+            .lines()
+            .map(|line| parse::threadtime(line).unwrap())
+            .collect();
+        assert_eq!(messages.len(), 3);
+        assert_eq!(messages[0].level(), Level::Warning);
+        assert_eq!(messages[0].tag(), "storage");
+        assert_eq!(messages[0].content(), "disk almost full");
+        assert_eq!(messages[0].process_id(), Some(std::process::id() as i32));
+        assert_eq!(messages[1].level(), Level::Error);
+        assert_eq!(messages[2].tag(), "logcat.logger.tests");
+        assert_eq!(messages[2].content(), "from a module");
+    }
+
+    #[test]
+    fn level() {
+        assert_eq!(Level::from(log::Level::Trace), Level::Verbose);
+        assert_eq!(Level::from(log::Level::Warn), Level::Warning);
+    }
+}
@@ -1,20 +1,52 @@
+mod buffer;
 mod builder;
 mod level;
 
+pub use buffer::{Buffer, ParseBufferError};
 pub use builder::{Error, MessageBuilder};
 use chrono::naive::{NaiveDate, NaiveDateTime, NaiveTime};
-pub use level::Level;
+pub use level::{Level, ParseLevelError};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
 /// A log message.
+///
+/// With the `serde` feature, messages are serialized with the fields
+/// `level`, `tag`, `content`, `date_time`, `pid`, `tid`, `uid` and `buffer`.
+/// The date and time is in ISO 8601 format, such as
+/// `2017-08-01T07:30:00.123`, and fields that are not available are omitted.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Message {
     level: Level,
     tag: String,
     content: String,
 
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
     date_time: Option<NaiveDateTime>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
     pid: Option<i32>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
     tid: Option<i32>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    uid: Option<i32>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    buffer: Option<Buffer>,
 }
 
 impl Message {
@@ -67,4 +99,24 @@ impl Message {
     pub fn thread_id(&self) -> Option<i32> {
         self.tid
     }
+
+    /// Returns the user ID of the process that logged this message.
+    ///
+    /// Returns `None` if the user ID is not available.
+    pub fn uid(&self) -> Option<i32> {
+        self.uid
+    }
+
+    /// Returns the buffer this message was logged to.
+    ///
+    /// Returns `None` if the buffer is not available.
+    pub fn buffer(&self) -> Option<Buffer> {
+        self.buffer
+    }
+
+    /// Appends a line of content to this message.
+    pub(crate) fn push_line(&mut self, line: &str) {
+        self.content.push('\n');
+        self.content.push_str(line);
+    }
 }
@@ -0,0 +1,117 @@
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+use thiserror::Error;
+
+/// The error type for parsing a [`Buffer`].
+#[derive(Debug, Error, Eq, PartialEq)]
+#[error("invalid buffer: `{0}`")]
+pub struct ParseBufferError(String);
+
+/// Log buffers.
+///
+/// With the `serde` feature, buffers are serialized by name.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(rename_all = "lowercase")
+)]
+pub enum Buffer {
+    Main,
+    Radio,
+    Events,
+    System,
+    Crash,
+    Stats,
+    Security,
+    Kernel,
+}
+
+impl Buffer {
+    /// Returns the name of this `Buffer`, as used by `logcat -b`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use logcat::message::Buffer;
+    ///
+    /// assert_eq!(Buffer::name(Buffer::Main), "main");
+    /// assert_eq!(Buffer::name(Buffer::Events), "events");
+    /// ```
+    pub fn name(self) -> &'static str {
+        match self {
+            Buffer::Main => "main",
+            Buffer::Radio => "radio",
+            Buffer::Events => "events",
+            Buffer::System => "system",
+            Buffer::Crash => "crash",
+            Buffer::Stats => "stats",
+            Buffer::Security => "security",
+            Buffer::Kernel => "kernel",
+        }
+    }
+}
+
+impl fmt::Display for Buffer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Buffer {
+    type Err = ParseBufferError;
+
+    fn from_str(s: &str) -> Result<Buffer, ParseBufferError> {
+        let buffer = match s {
+            "main" => Buffer::Main,
+            "radio" => Buffer::Radio,
+            "events" => Buffer::Events,
+            "system" => Buffer::System,
+            "crash" => Buffer::Crash,
+            "stats" => Buffer::Stats,
+            "security" => Buffer::Security,
+            "kernel" => Buffer::Kernel,
+            _ => return Err(ParseBufferError(s.to_owned())),
+        };
+        Ok(buffer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::message::buffer::{Buffer, ParseBufferError};
+
+    #[test]
+    fn buffer() {
+        let buffers = [
+            Buffer::Main,
+            Buffer::Radio,
+            Buffer::Events,
+            Buffer::System,
+            Buffer::Crash,
+            Buffer::Stats,
+            Buffer::Security,
+            Buffer::Kernel,
+        ];
+
+        for buffer in &buffers {
+            assert_eq!(buffer.name().parse(), Ok(*buffer));
+        }
+        assert_eq!(
+            "all".parse::<Buffer>(),
+            Err(ParseBufferError("all".to_owned()))
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn buffer_serde() {
+        assert_eq!(serde_json::to_string(&Buffer::Main).unwrap(), r#""main""#);
+        assert_eq!(
+            serde_json::from_str::<Buffer>(r#""events""#).unwrap(),
+            Buffer::Events
+        );
+    }
+}
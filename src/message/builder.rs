@@ -1,5 +1,7 @@
-use crate::message::{Level, Message};
+use crate::message::{Buffer, Level, Message};
 use chrono::naive::NaiveDateTime;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 use std::cell::RefCell;
 use thiserror::Error;
 
@@ -12,7 +14,11 @@ pub enum Error {
 }
 
 /// Builds [`Message`] in parts.
+///
+/// With the `serde` feature, builders are serialized like [`Message`], with
+/// fields that are not set as `null`.
 #[derive(Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]
 pub struct MessageBuilder {
     // Mandatory
     level: RefCell<Option<Level>>,
@@ -23,6 +29,8 @@ pub struct MessageBuilder {
     date_time: RefCell<Option<NaiveDateTime>>,
     pid: RefCell<Option<i32>>,
     tid: RefCell<Option<i32>>,
+    uid: RefCell<Option<i32>>,
+    buffer: RefCell<Option<Buffer>>,
 }
 
 impl MessageBuilder {
@@ -67,6 +75,18 @@ impl MessageBuilder {
         self
     }
 
+    /// Sets the optional message user ID.
+    pub fn uid(&mut self, value: i32) -> &mut Self {
+        *self.uid.borrow_mut() = Some(value);
+        self
+    }
+
+    /// Sets the optional message buffer.
+    pub fn buffer(&mut self, value: Buffer) -> &mut Self {
+        *self.buffer.borrow_mut() = Some(value);
+        self
+    }
+
     /// Builds and returns the Message.
     ///
     /// An error may be returned if one or more required fields were not set.
@@ -88,15 +108,36 @@ impl MessageBuilder {
             date_time: *self.date_time.borrow(),
             pid: *self.pid.borrow(),
             tid: *self.tid.borrow(),
+            uid: *self.uid.borrow(),
+            buffer: *self.buffer.borrow(),
         })
     }
 }
 
+impl From<&Message> for MessageBuilder {
+    /// Creates a `MessageBuilder` with the fields of `message`, to build a
+    /// modified copy of it.
+    fn from(message: &Message) -> MessageBuilder {
+        MessageBuilder {
+            level: RefCell::new(Some(message.level)),
+            tag: RefCell::new(Some(message.tag.clone())),
+            content: RefCell::new(Some(message.content.clone())),
+            date_time: RefCell::new(message.date_time),
+            pid: RefCell::new(message.pid),
+            tid: RefCell::new(message.tid),
+            uid: RefCell::new(message.uid),
+            buffer: RefCell::new(message.buffer),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    #[cfg(feature = "serde")]
+    use crate::message::Message;
     use crate::message::{
         builder::{Error, MessageBuilder},
-        Level,
+        Buffer, Level,
     };
     use chrono::{Datelike, NaiveDate, Timelike};
 
@@ -116,6 +157,8 @@ mod tests {
         assert_eq!(m.time(), None);
         assert_eq!(m.process_id(), None);
         assert_eq!(m.thread_id(), None);
+        assert_eq!(m.uid(), None);
+        assert_eq!(m.buffer(), None);
     }
 
     #[test]
@@ -132,6 +175,8 @@ mod tests {
             )
             .process_id(1)
             .thread_id(2)
+            .uid(10012)
+            .buffer(Buffer::Main)
             .build()
             .unwrap();
 
@@ -151,6 +196,16 @@ mod tests {
 
         assert_eq!(m.process_id().unwrap(), 1);
         assert_eq!(m.thread_id().unwrap(), 2);
+        assert_eq!(m.uid().unwrap(), 10012);
+        assert_eq!(m.buffer().unwrap(), Buffer::Main);
+
+        let copy = MessageBuilder::from(&m)
+            .buffer(Buffer::Radio)
+            .build()
+            .unwrap();
+        assert_eq!(copy.buffer(), Some(Buffer::Radio));
+        assert_eq!(copy.date_time(), m.date_time());
+        assert_eq!(copy.uid(), m.uid());
     }
 
     #[test]
@@ -172,4 +227,39 @@ mod tests {
             Err(Error::FieldNotSet("level"))
         );
     }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn message_serde() {
+        let m = MessageBuilder::new()
+            .level(Level::Warning)
+            .tag("tag")
+            .content("content")
+            .date_time(
+                NaiveDate::from_ymd_opt(2017, 8, 1)
+                    .unwrap()
+                    .and_hms_milli_opt(7, 30, 0, 123)
+                    .unwrap(),
+            )
+            .process_id(1)
+            .buffer(Buffer::Main)
+            .build()
+            .unwrap();
+
+        let json = serde_json::to_string(&m).unwrap();
+        assert_eq!(
+            json,
+            r#"{"level":"W","tag":"tag","content":"content","date_time":"2017-08-01T07:30:00.123","pid":1,"buffer":"main"}"#
+        );
+        assert_eq!(serde_json::from_str::<Message>(&json).unwrap(), m);
+
+        let builder: MessageBuilder =
+            serde_json::from_str(r#"{"level":"Warning","tag":"tag"}"#).unwrap();
+        assert_eq!(builder.build(), Err(Error::FieldNotSet("content")));
+        builder.content.replace(Some("content".to_owned()));
+        let json = serde_json::to_string(&builder).unwrap();
+        assert!(
+            json.starts_with(r#"{"level":"W","tag":"tag","content":"content","date_time":null"#)
+        );
+    }
 }
@@ -1,43 +1,67 @@
-/// Logging levels.
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg(feature = "serde")]
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::str::FromStr;
+use thiserror::Error;
+
+/// The error type for converting into a [`Level`].
+#[derive(Debug, Error, Eq, PartialEq)]
+#[error("invalid level: `{0}`")]
+pub struct ParseLevelError(String);
+
+/// Logging levels, ordered by their Android log priority.
+///
+/// `Unknown`, `Default` and `Silent` are not used by messages logged with
+/// `android.util.Log`, but appear in binary logs and filterspecs.
+///
+/// With the `serde` feature, levels are serialized as their short
+/// description, such as `"W"`, except for `"Unknown"` and `"Default"` which
+/// share a short description. Short and long descriptions and priorities,
+/// such as `4`, are accepted when deserializing.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum Level {
-    Verbose,
-    Debug,
-    Info,
-    Warning,
-    Error,
-    Fatal,
+    Unknown = 0,
+    Default = 1,
+    Verbose = 2,
+    Debug = 3,
+    Info = 4,
+    Warning = 5,
+    Error = 6,
+    Fatal = 7,
+    Silent = 8,
 }
 
 impl Level {
     /// Returns `true` if `Debug`, `Info`, `Warning`, `Error`, or `Fatal`.
     pub fn is_debug_or_higher(self) -> bool {
-        matches!(
-            self,
-            Level::Debug | Level::Info | Level::Warning | Level::Error | Level::Fatal
-        )
+        (Level::Debug..=Level::Fatal).contains(&self)
     }
 
     /// Returns `true` if `Info`, `Warning`, `Error`, or `Fatal`.
     pub fn is_info_or_higher(self) -> bool {
-        matches!(
-            self,
-            Level::Info | Level::Warning | Level::Error | Level::Fatal
-        )
+        (Level::Info..=Level::Fatal).contains(&self)
     }
 
     /// Returns `true` if `Warning`, `Error`, or `Fatal`.
     pub fn is_warning_or_higher(self) -> bool {
-        matches!(self, Level::Warning | Level::Error | Level::Fatal)
+        (Level::Warning..=Level::Fatal).contains(&self)
     }
 
     /// Returns `true` if `Error` or `Fatal`.
     pub fn is_error_or_higher(self) -> bool {
-        matches!(self, Level::Error | Level::Fatal)
+        (Level::Error..=Level::Fatal).contains(&self)
+    }
+
+    /// Returns the Android log priority for this `Level`, such as `2` for
+    /// `Verbose`.
+    pub fn priority(self) -> u8 {
+        self as u8
     }
 
     /// Returns the short description for this `Level`.
     ///
+    /// `Unknown` and `Default` are both described as `?`, as by logcat.
+    ///
     /// # Examples
     ///
     /// ```
@@ -49,22 +73,192 @@ impl Level {
     /// assert_eq!(Level::short(Level::Warning), "W");
     /// assert_eq!(Level::short(Level::Error), "E");
     /// assert_eq!(Level::short(Level::Fatal), "F");
+    /// assert_eq!(Level::short(Level::Silent), "S");
     /// ```
     pub fn short(self) -> &'static str {
         match self {
+            Level::Unknown | Level::Default => "?",
             Level::Verbose => "V",
             Level::Debug => "D",
             Level::Info => "I",
             Level::Warning => "W",
             Level::Error => "E",
             Level::Fatal => "F",
+            Level::Silent => "S",
+        }
+    }
+
+    /// Returns the long description for this `Level`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use logcat::message::Level;
+    ///
+    /// assert_eq!(Level::long(Level::Verbose), "Verbose");
+    /// assert_eq!(Level::long(Level::Warning), "Warning");
+    /// ```
+    pub fn long(self) -> &'static str {
+        match self {
+            Level::Unknown => "Unknown",
+            Level::Default => "Default",
+            Level::Verbose => "Verbose",
+            Level::Debug => "Debug",
+            Level::Info => "Info",
+            Level::Warning => "Warning",
+            Level::Error => "Error",
+            Level::Fatal => "Fatal",
+            Level::Silent => "Silent",
+        }
+    }
+}
+
+/// Formats the short description, or the long description with `{:#}`.
+///
+/// # Examples
+///
+/// ```
+/// use logcat::message::Level;
+///
+/// assert_eq!(format!("{}", Level::Info), "I");
+/// assert_eq!(format!("{:#}", Level::Info), "Info");
+/// ```
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if f.alternate() {
+            f.pad(self.long())
+        } else {
+            f.pad(self.short())
+        }
+    }
+}
+
+/// Converts a short description, such as `W`, ignoring case.
+impl TryFrom<char> for Level {
+    type Error = ParseLevelError;
+
+    fn try_from(c: char) -> Result<Level, ParseLevelError> {
+        let level = match c.to_ascii_uppercase() {
+            '?' => Level::Unknown,
+            'V' => Level::Verbose,
+            'D' => Level::Debug,
+            'I' => Level::Info,
+            'W' => Level::Warning,
+            'E' => Level::Error,
+            'F' => Level::Fatal,
+            'S' => Level::Silent,
+            _ => return Err(ParseLevelError(c.to_string())),
+        };
+        Ok(level)
+    }
+}
+
+/// Converts an Android log priority, such as `5` for `Warning`.
+impl TryFrom<u8> for Level {
+    type Error = ParseLevelError;
+
+    fn try_from(priority: u8) -> Result<Level, ParseLevelError> {
+        let level = match priority {
+            0 => Level::Unknown,
+            1 => Level::Default,
+            2 => Level::Verbose,
+            3 => Level::Debug,
+            4 => Level::Info,
+            5 => Level::Warning,
+            6 => Level::Error,
+            7 => Level::Fatal,
+            8 => Level::Silent,
+            _ => return Err(ParseLevelError(priority.to_string())),
+        };
+        Ok(level)
+    }
+}
+
+/// Parses a short or long description, such as `W` or `Warning`, ignoring
+/// case. The `android.util.Log` names `warn` and `assert` are also accepted.
+impl FromStr for Level {
+    type Err = ParseLevelError;
+
+    fn from_str(s: &str) -> Result<Level, ParseLevelError> {
+        let mut chars = s.chars();
+        if let (Some(c), None) = (chars.next(), chars.next()) {
+            return Level::try_from(c);
+        }
+
+        let level = match s.to_ascii_lowercase().as_str() {
+            "unknown" => Level::Unknown,
+            "default" => Level::Default,
+            "verbose" => Level::Verbose,
+            "debug" => Level::Debug,
+            "info" => Level::Info,
+            "warning" | "warn" => Level::Warning,
+            "error" => Level::Error,
+            "fatal" | "assert" => Level::Fatal,
+            "silent" => Level::Silent,
+            _ => return Err(ParseLevelError(s.to_owned())),
+        };
+        Ok(level)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl Serialize for Level {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Level::Unknown | Level::Default => serializer.serialize_str(self.long()),
+            _ => serializer.serialize_str(self.short()),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for Level {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Level, D::Error> {
+        struct Visitor;
+
+        impl de::Visitor<'_> for Visitor {
+            type Value = Level;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("a level description or priority")
+            }
+
+            fn visit_str<E: de::Error>(self, s: &str) -> Result<Level, E> {
+                s.parse().map_err(E::custom)
+            }
+
+            fn visit_u64<E: de::Error>(self, priority: u64) -> Result<Level, E> {
+                u8::try_from(priority)
+                    .map_err(E::custom)
+                    .and_then(|priority| Level::try_from(priority).map_err(E::custom))
+            }
+
+            fn visit_i64<E: de::Error>(self, priority: i64) -> Result<Level, E> {
+                u64::try_from(priority)
+                    .map_err(E::custom)
+                    .and_then(|priority| self.visit_u64(priority))
+            }
         }
+
+        deserializer.deserialize_any(Visitor)
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::message::Level;
+    use crate::message::level::{Level, ParseLevelError};
+
+    const LEVELS: [Level; 9] = [
+        Level::Unknown,
+        Level::Default,
+        Level::Verbose,
+        Level::Debug,
+        Level::Info,
+        Level::Warning,
+        Level::Error,
+        Level::Fatal,
+        Level::Silent,
+    ];
 
     #[test]
     fn level() {
@@ -95,5 +289,75 @@ mod tests {
         assert!(!Level::is_error_or_higher(Level::Warning));
         assert!(Level::is_error_or_higher(Level::Error));
         assert!(Level::is_error_or_higher(Level::Fatal));
+
+        for level in [Level::Unknown, Level::Default, Level::Silent] {
+            assert!(!level.is_debug_or_higher());
+            assert!(!level.is_error_or_higher());
+        }
+    }
+
+    #[test]
+    fn level_ordering() {
+        for pair in LEVELS.windows(2) {
+            assert!(pair[0] < pair[1]);
+        }
+    }
+
+    #[test]
+    fn level_priority() {
+        for (priority, level) in LEVELS.iter().enumerate() {
+            assert_eq!(level.priority() as usize, priority);
+            assert_eq!(Level::try_from(priority as u8), Ok(*level));
+        }
+        assert_eq!(Level::try_from(9u8), Err(ParseLevelError("9".to_owned())));
+    }
+
+    #[test]
+    fn level_parse() {
+        for level in &LEVELS[2..] {
+            assert_eq!(level.short().parse(), Ok(*level));
+            assert_eq!(level.long().parse(), Ok(*level));
+            assert_eq!(level.long().to_uppercase().parse(), Ok(*level));
+            assert_eq!(format!("{:#}", level).parse(), Ok(*level));
+        }
+        assert_eq!("?".parse(), Ok(Level::Unknown));
+        assert_eq!("default".parse(), Ok(Level::Default));
+        assert_eq!("w".parse(), Ok(Level::Warning));
+        assert_eq!("warn".parse(), Ok(Level::Warning));
+        assert_eq!("assert".parse(), Ok(Level::Fatal));
+        assert_eq!("X".parse::<Level>(), Err(ParseLevelError("X".to_owned())));
+        assert_eq!("".parse::<Level>(), Err(ParseLevelError("".to_owned())));
+    }
+
+    #[test]
+    fn level_display() {
+        assert_eq!(Level::Error.to_string(), "E");
+        assert_eq!(format!("{:#}", Level::Error), "Error");
+        assert_eq!(format!("{:>3}", Level::Error), "  E");
+        assert_eq!(format!("{}", Level::Default), "?");
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn level_serde() {
+        for level in &LEVELS {
+            let json = serde_json::to_string(level).unwrap();
+            assert_eq!(serde_json::from_str::<Level>(&json).unwrap(), *level);
+        }
+        assert_eq!(serde_json::to_string(&Level::Info).unwrap(), r#""I""#);
+        assert_eq!(
+            serde_json::to_string(&Level::Default).unwrap(),
+            r#""Default""#
+        );
+        assert_eq!(
+            serde_json::from_str::<Level>(r#""warning""#).unwrap(),
+            Level::Warning
+        );
+        assert!(serde_json::from_str::<Level>(r#""X""#).is_err());
+
+        // Priorities.
+        assert_eq!(serde_json::from_str::<Level>("4").unwrap(), Level::Info);
+        assert!(serde_json::from_str::<Level>("9").is_err());
+        assert!(serde_json::from_str::<Level>("-1").is_err());
     }
 }
@@ -0,0 +1,302 @@
+//! Mapping of messages to the OpenTelemetry log data model.
+//!
+//! A [`LogRecord`] is built from a [`Message`] as follows:
+//!
+//! | Log record field | Message                                  |
+//! |------------------|------------------------------------------|
+//! | `Timestamp`      | date and time, taken as UTC              |
+//! | `SeverityNumber` | level, see [`severity_number`]           |
+//! | `SeverityText`   | long description of the level, `Warning` |
+//! | `Body`           | content                                  |
+//! | `Attributes`     | tag, pid, tid, uid and buffer            |
+//!
+//! The attributes are named `android.log.tag`, `process.pid`, `thread.id`,
+//! `android.uid` and `android.log.buffer`, and are omitted when not known.
+//!
+//! [`Encoder`] encodes log records as an OTLP/JSON `ExportLogsServiceRequest`,
+//! as posted to the `/v1/logs` endpoint of a collector.
+//!
+//! # Examples
+//!
+//! ```
+//! use logcat::{otel::Encoder, parse};
+//!
+//! let line = "2017-08-01 07:30:00.123  1234  1250 W tag     : content";
+//! let msg = parse::threadtime(line).unwrap();
+//!
+//! let mut encoder = Encoder::new();
+//! encoder.resource_attribute("service.name", "device");
+//! let request = encoder.encode(&[msg]);
+//! assert!(request.contains(r#""severityNumber":13"#));
+//! ```
+
+use crate::message::{Level, Message};
+use serde_json::{json, Value};
+use std::io::{self, Write};
+
+/// Returns the OpenTelemetry severity number for `level`.
+///
+/// `Verbose` maps to `TRACE` (1), `Debug` to `DEBUG` (5), `Info` to `INFO`
+/// (9), `Warning` to `WARN` (13), `Error` to `ERROR` (17) and `Fatal` to
+/// `FATAL` (21). Other levels are unspecified (0).
+pub fn severity_number(level: Level) -> u8 {
+    match level {
+        Level::Verbose => 1,
+        Level::Debug => 5,
+        Level::Info => 9,
+        Level::Warning => 13,
+        Level::Error => 17,
+        Level::Fatal => 21,
+        Level::Unknown | Level::Default | Level::Silent => 0,
+    }
+}
+
+/// A value of a log record attribute.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AnyValue {
+    String(String),
+    Int(i64),
+}
+
+/// A log record in the OpenTelemetry log data model.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LogRecord {
+    time_unix_nano: Option<i64>,
+    severity_number: u8,
+    severity_text: &'static str,
+    body: String,
+    attributes: Vec<(&'static str, AnyValue)>,
+}
+
+impl LogRecord {
+    /// Returns the nanoseconds since the Unix epoch, if the message has a
+    /// timestamp.
+    pub fn time_unix_nano(&self) -> Option<i64> {
+        self.time_unix_nano
+    }
+
+    /// Returns the severity number, see [`severity_number`].
+    pub fn severity_number(&self) -> u8 {
+        self.severity_number
+    }
+
+    /// Returns the severity text, such as `Warning`.
+    pub fn severity_text(&self) -> &str {
+        self.severity_text
+    }
+
+    /// Returns the body.
+    pub fn body(&self) -> &str {
+        &self.body
+    }
+
+    /// Returns the attributes.
+    pub fn attributes(&self) -> &[(&'static str, AnyValue)] {
+        &self.attributes
+    }
+
+    fn to_json(&self) -> Value {
+        let mut record = json!({
+            "severityNumber": self.severity_number,
+            "severityText": self.severity_text,
+            "body": { "stringValue": self.body },
+            "attributes": self
+                .attributes
+                .iter()
+                .map(|(key, value)| attribute(key, value))
+                .collect::<Vec<_>>(),
+        });
+        if let Some(time) = self.time_unix_nano {
+            // 64-bit integers are strings in OTLP/JSON.
+            record["timeUnixNano"] = json!(time.to_string());
+        }
+        record
+    }
+}
+
+impl From<&Message> for LogRecord {
+    fn from(msg: &Message) -> LogRecord {
+        let mut attributes = vec![("android.log.tag", AnyValue::String(msg.tag().to_owned()))];
+        if let Some(pid) = msg.process_id() {
+            attributes.push(("process.pid", AnyValue::Int(pid.into())));
+        }
+        if let Some(tid) = msg.thread_id() {
+            attributes.push(("thread.id", AnyValue::Int(tid.into())));
+        }
+        if let Some(uid) = msg.uid() {
+            attributes.push(("android.uid", AnyValue::Int(uid.into())));
+        }
+        if let Some(buffer) = msg.buffer() {
+            attributes.push((
+                "android.log.buffer",
+                AnyValue::String(buffer.name().to_owned()),
+            ));
+        }
+
+        LogRecord {
+            time_unix_nano: msg
+                .date_time()
+                .and_then(|date_time| date_time.and_utc().timestamp_nanos_opt()),
+            severity_number: severity_number(msg.level()),
+            severity_text: msg.level().long(),
+            body: msg.content().to_owned(),
+            attributes,
+        }
+    }
+}
+
+fn attribute(key: &str, value: &AnyValue) -> Value {
+    let value = match value {
+        AnyValue::String(value) => json!({ "stringValue": value }),
+        AnyValue::Int(value) => json!({ "intValue": value.to_string() }),
+    };
+    json!({ "key": key, "value": value })
+}
+
+/// Encodes messages as OTLP/JSON export requests.
+#[derive(Clone, Debug)]
+pub struct Encoder {
+    resource: Vec<(String, AnyValue)>,
+    scope: String,
+}
+
+impl Encoder {
+    /// Creates a new `Encoder` without resource attributes and with the
+    /// instrumentation scope `logcat`.
+    pub fn new() -> Encoder {
+        Encoder {
+            resource: Vec::new(),
+            scope: "logcat".to_owned(),
+        }
+    }
+
+    /// Adds a string attribute to the resource, such as `service.name` or
+    /// `device.id`.
+    pub fn resource_attribute(&mut self, key: &str, value: &str) -> &mut Self {
+        self.resource
+            .push((key.to_owned(), AnyValue::String(value.to_owned())));
+        self
+    }
+
+    /// Sets the name of the instrumentation scope.
+    pub fn scope(&mut self, name: &str) -> &mut Self {
+        self.scope = name.to_owned();
+        self
+    }
+
+    /// Returns an `ExportLogsServiceRequest` with all `messages`.
+    pub fn encode(&self, messages: &[Message]) -> String {
+        self.to_json(messages).to_string()
+    }
+
+    /// Writes an `ExportLogsServiceRequest` with all `messages`, followed by
+    /// a newline.
+    pub fn write<W: Write>(&self, out: &mut W, messages: &[Message]) -> io::Result<()> {
+        serde_json::to_writer(&mut *out, &self.to_json(messages))?;
+        out.write_all(b"\n")
+    }
+
+    fn to_json(&self, messages: &[Message]) -> Value {
+        let records: Vec<_> = messages
+            .iter()
+            .map(|msg| LogRecord::from(msg).to_json())
+            .collect();
+        let resource: Vec<_> = self
+            .resource
+            .iter()
+            .map(|(key, value)| attribute(key, value))
+            .collect();
+        json!({
+            "resourceLogs": [{
+                "resource": { "attributes": resource },
+                "scopeLogs": [{
+                    "scope": { "name": self.scope },
+                    "logRecords": records,
+                }],
+            }],
+        })
+    }
+}
+
+impl Default for Encoder {
+    fn default() -> Encoder {
+        Encoder::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::message::{Buffer, Level, MessageBuilder};
+    use crate::otel::{severity_number, AnyValue, Encoder, LogRecord};
+    use crate::parse;
+    use serde_json::{json, Value};
+
+    #[test]
+    fn log_record() {
+        let msg =
+            parse::threadtime("2017-08-01 07:30:00.123  1234  1250 E tag     : content").unwrap();
+        let record = LogRecord::from(&msg);
+        assert_eq!(record.time_unix_nano(), Some(1_501_572_600_123_000_000));
+        assert_eq!(record.severity_number(), 17);
+        assert_eq!(record.severity_text(), "Error");
+        assert_eq!(record.body(), "content");
+        assert_eq!(
+            record.attributes(),
+            [
+                ("android.log.tag", AnyValue::String("tag".to_owned())),
+                ("process.pid", AnyValue::Int(1234)),
+                ("thread.id", AnyValue::Int(1250)),
+            ]
+        );
+
+        assert_eq!(severity_number(Level::Verbose), 1);
+        assert_eq!(severity_number(Level::Fatal), 21);
+        assert_eq!(severity_number(Level::Silent), 0);
+    }
+
+    #[test]
+    fn encoder() {
+        let msg = MessageBuilder::new()
+            .level(Level::Info)
+            .tag("tag")
+            .content("content")
+            .uid(10012)
+            .buffer(Buffer::Main)
+            .build()
+            .unwrap();
+
+        let mut encoder = Encoder::new();
+        encoder
+            .resource_attribute("service.name", "device")
+            .scope("test");
+        let mut out = Vec::new();
+        encoder.write(&mut out, &[msg]).unwrap();
+
+        let request: Value = serde_json::from_slice(&out).unwrap();
+        assert_eq!(
+            request,
+            json!({
+                "resourceLogs": [{
+                    "resource": {
+                        "attributes": [
+                            { "key": "service.name", "value": { "stringValue": "device" } },
+                        ],
+                    },
+                    "scopeLogs": [{
+                        "scope": { "name": "test" },
+                        "logRecords": [{
+                            "severityNumber": 9,
+                            "severityText": "Info",
+                            "body": { "stringValue": "content" },
+                            "attributes": [
+                                { "key": "android.log.tag", "value": { "stringValue": "tag" } },
+                                { "key": "android.uid", "value": { "intValue": "10012" } },
+                                { "key": "android.log.buffer", "value": { "stringValue": "main" } },
+                            ],
+                        }],
+                    }],
+                }],
+            })
+        );
+    }
+}
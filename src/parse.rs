@@ -1,5 +1,11 @@
+mod iter;
+#[cfg(feature = "json")]
+mod json;
 mod parser;
 mod threadtime;
 
+pub use iter::MessageIterator;
+#[cfg(feature = "json")]
+pub use json::{json, JsonParser};
 pub use parser::Parser;
 pub use threadtime::{threadtime, ThreadTimeParser};
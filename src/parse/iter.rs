@@ -0,0 +1,74 @@
+use crate::decompress;
+use crate::message::Message;
+use crate::parse::parser::Parser;
+use std::io::{self, BufRead};
+use std::path::Path;
+
+/// Iterates over the messages parsed from the lines of a reader.
+///
+/// Lines that fail to parse, such as `--------- beginning of main`, are
+/// skipped. Invalid UTF-8 is replaced.
+///
+/// # Examples
+///
+/// ```
+/// use logcat::parse::{MessageIterator, ThreadTimeParser};
+///
+/// let source = "--------- beginning of main
+/// 08-01 07:30:00.123  1234  1250 W tag     : content
+/// ";
+/// let messages: Vec<_> = MessageIterator::new(source.as_bytes(), ThreadTimeParser::new())
+///     .collect::<Result<_, _>>()
+///     .unwrap();
+/// assert_eq!(messages.len(), 1);
+/// ```
+pub struct MessageIterator<R, P> {
+    reader: R,
+    parser: P,
+    line: Vec<u8>,
+}
+
+impl<R: BufRead, P: Parser> MessageIterator<R, P> {
+    /// Creates a new `MessageIterator` parsing the lines of `reader` with
+    /// `parser`.
+    pub fn new(reader: R, parser: P) -> MessageIterator<R, P> {
+        MessageIterator {
+            reader,
+            parser,
+            line: Vec::new(),
+        }
+    }
+
+    /// Returns the parser.
+    pub fn into_parser(self) -> P {
+        self.parser
+    }
+}
+
+impl<P: Parser> MessageIterator<Box<dyn BufRead + Send>, P> {
+    /// Opens the file at `path`, decompressing it if needed as by
+    /// [`decompress::open`].
+    pub fn open<T: AsRef<Path>>(path: T, parser: P) -> io::Result<Self> {
+        Ok(MessageIterator::new(decompress::open(path)?, parser))
+    }
+}
+
+impl<R: BufRead, P: Parser> Iterator for MessageIterator<R, P> {
+    type Item = io::Result<Message>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            self.line.clear();
+            match self.reader.read_until(b'\n', &mut self.line) {
+                Ok(0) => return None,
+                Ok(_) => {}
+                Err(e) => return Some(Err(e)),
+            }
+            let line = String::from_utf8_lossy(&self.line);
+            let line = line.trim_end_matches(['\n', '\r']);
+            if let Ok(msg) = self.parser.parse(line) {
+                return Some(Ok(msg));
+            }
+        }
+    }
+}
@@ -0,0 +1,54 @@
+use crate::message::Message;
+use crate::parse::parser::Parser;
+use anyhow::Result;
+
+/// Parses a JSON Lines object into a message.
+///
+/// See [`json`](crate::json) for the fields of the object.
+///
+/// # Examples
+///
+/// ```
+/// use logcat::parse;
+///
+/// let line = r#"{"level":"W","tag":"tag","content":"content","pid":1234}"#;
+/// let message = parse::json(line).unwrap();
+/// assert_eq!(message.process_id(), Some(1234));
+/// ```
+pub fn json(line: &str) -> Result<Message> {
+    JsonParser::new().parse(line)
+}
+
+/// Parses lines written by [`json::Writer`](crate::json::Writer).
+#[derive(Default)]
+pub struct JsonParser;
+
+impl JsonParser {
+    /// Creates a new `JsonParser`.
+    pub fn new() -> JsonParser {
+        JsonParser
+    }
+}
+
+impl Parser for JsonParser {
+    fn parse(&mut self, line: &str) -> Result<Message> {
+        Ok(serde_json::from_str(line.trim())?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{message::Level, parse};
+
+    #[test]
+    fn json() {
+        let msg = parse::json(r#" {"level":"Debug","tag":"t","content":"c","tid":7} "#).unwrap();
+        assert_eq!(msg.level(), Level::Debug);
+        assert_eq!(msg.tag(), "t");
+        assert_eq!(msg.content(), "c");
+        assert_eq!(msg.thread_id(), Some(7));
+        assert_eq!(msg.date_time(), None);
+
+        assert!(parse::json("12-31 22:59:41.271     1   197 I init    : x").is_err());
+    }
+}
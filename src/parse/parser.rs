@@ -7,7 +7,7 @@ pub trait Parser {
     ///
     /// Returns `None` if parsing failed.
     ///
-    /// This trait is usually used with a `MessageIterator<T>` and not
+    /// This trait is usually used with a [`MessageIterator`](crate::parse::MessageIterator) and not
     /// used directly.
     fn parse(&mut self, line: &str) -> Result<Message>;
 }
@@ -25,7 +25,9 @@ struct PartialMessage {
     hour: u32,
     minute: u32,
     second: u32,
-    millisecond: u32,
+    nanosecond: u32,
+    year: Option<i32>,
+    uid: Option<i32>,
     pid: i32,
     tid: i32,
     level: Level,
@@ -40,7 +42,9 @@ impl Default for PartialMessage {
             hour: 0,
             minute: 0,
             second: 0,
-            millisecond: 0,
+            nanosecond: 0,
+            year: None,
+            uid: None,
             pid: 0,
             tid: 0,
             level: Level::Verbose,
@@ -49,16 +53,28 @@ impl Default for PartialMessage {
     }
 }
 
+/// Parses lines in the `threadtime` format, with or without modifiers.
+#[derive(Default)]
 pub struct ThreadTimeParser {
     msg: PartialMessage,
+    year: Option<i32>,
 }
 
 impl ThreadTimeParser {
-    fn new() -> ThreadTimeParser {
+    /// Creates a new `ThreadTimeParser`, dating lines without a year in the
+    /// current year.
+    pub fn new() -> ThreadTimeParser {
         ThreadTimeParser {
             msg: PartialMessage::default(),
+            year: None,
         }
     }
+
+    /// Sets the year of lines without one, instead of the current year.
+    pub fn year(&mut self, value: i32) -> &mut Self {
+        self.year = Some(value);
+        self
+    }
 }
 
 impl Parser for ThreadTimeParser {
@@ -70,9 +86,13 @@ impl Parser for ThreadTimeParser {
 
         // The `line` is expected to look like:
         //   mm-dd hh:mm:ss.mmm pid tid level tag: content
+        // or, with the year, usec, nsec, zone and uid modifiers:
+        //   yyyy-mm-dd hh:mm:ss.uuuuuu +zzzz uid pid tid level tag: content
         self.msg = PartialMessage::default();
         self.parse_date(line)
             .and_then(|x| self.parse_time(x))
+            .map(|x| self.parse_zone(x))
+            .and_then(|x| self.parse_uid(x))
             .and_then(|x| self.parse_pid(x))
             .and_then(|x| self.parse_tid(x))
             .and_then(|x| self.parse_level(x))
@@ -91,7 +111,16 @@ impl ThreadTimeParser {
             .context("invalid line: no groups after date")?;
 
         let mut parse = || -> Result<&'a str> {
-            let (month, day) = month_day.split_once('-').context("'-' not found")?;
+            let groups: Vec<_> = month_day.split('-').collect();
+            let (year, month, day) = match groups[..] {
+                [month, day] => (None, month, day),
+                // yyyy-mm-dd
+                [year, month, day] => (Some(year), month, day),
+                _ => bail!("'-' not found"),
+            };
+            if let Some(year) = year {
+                self.msg.year = Some(year.parse()?);
+            }
             self.msg.month = month.parse()?;
             self.msg.day = day.parse()?;
             Ok(rest)
@@ -112,12 +141,55 @@ impl ThreadTimeParser {
             self.msg.hour = splitter.next().context("not enough groups")?.parse()?;
             self.msg.minute = splitter.next().context("not enough groups")?.parse()?;
             self.msg.second = splitter.next().context("not enough groups")?.parse()?;
-            self.msg.millisecond = splitter.next().context("not enough groups")?.parse()?;
+
+            // The fraction is in milliseconds, or in microseconds or
+            // nanoseconds with the usec or nsec modifiers.
+            let fraction = splitter.next().context("not enough groups")?;
+            if fraction.len() > 9 {
+                bail!("fraction too long");
+            }
+            let scale = 10u32.pow(9 - fraction.len() as u32);
+            self.msg.nanosecond = fraction.parse::<u32>()? * scale;
             Ok(rest)
         };
         parse().with_context(|| format!("invalid time: {}", time))
     }
 
+    fn parse_zone<'a>(&mut self, rest: &'a str) -> &'a str {
+        // An optional time zone, such as +0100.
+        let trimmed = rest.trim_start();
+        match trimmed.split_once(char::is_whitespace) {
+            Some((zone, rest))
+                if zone.len() == 5
+                    && zone.starts_with(&['+', '-'][..])
+                    && zone[1..].bytes().all(|b| b.is_ascii_digit()) =>
+            {
+                rest
+            }
+            _ => rest,
+        }
+    }
+
+    fn parse_uid<'a>(&mut self, rest: &'a str) -> Result<&'a str> {
+        // An optional uid precedes the pid, and is detected by the level not
+        // being the third group.
+        let mut groups = rest.split_whitespace();
+        let has_uid = groups
+            .nth(2)
+            .is_some_and(|group| logcat_level(group).is_none());
+        if !has_uid {
+            return Ok(rest);
+        }
+
+        let (uid, rest) = rest
+            .trim_start()
+            .split_once(char::is_whitespace)
+            .context("invalid line: no groups after uid")?;
+        // Names such as `root` are used for short user names.
+        self.msg.uid = uid.parse().ok();
+        Ok(rest)
+    }
+
     fn parse_pid<'a>(&mut self, mut rest: &'a str) -> Result<&'a str> {
         rest = rest.trim_start();
 
@@ -148,18 +220,8 @@ impl ThreadTimeParser {
         let (level, rest) = rest
             .split_once(char::is_whitespace)
             .context("invalid line: no groups after level")?;
-        self.msg.level = match level.chars().next() {
-            Some(level) => match level {
-                'V' => Level::Verbose,
-                'D' => Level::Debug,
-                'I' => Level::Info,
-                'W' => Level::Warning,
-                'E' => Level::Error,
-                'F' => Level::Fatal,
-                _ => bail!("invalid level: {}", level),
-            },
-            None => bail!("invalid level: {}", level),
-        };
+        self.msg.level =
+            logcat_level(level).with_context(|| format!("invalid level: {}", level))?;
         Ok(rest)
     }
 
@@ -177,31 +239,54 @@ impl ThreadTimeParser {
     }
 
     fn parse_content(&mut self, rest: &str) -> Result<Message> {
-        let year = Local::now().year();
+        let year = self
+            .msg
+            .year
+            .or(self.year)
+            .unwrap_or_else(|| Local::now().year());
         let datetime = NaiveDate::from_ymd_opt(year, self.msg.month, self.msg.day)
             .context("invalid date")?
-            .and_hms_milli_opt(
+            .and_hms_nano_opt(
                 self.msg.hour,
                 self.msg.minute,
                 self.msg.second,
-                self.msg.millisecond,
+                self.msg.nanosecond,
             )
             .context("invalid time")?;
 
-        let message = MessageBuilder::new()
+        let mut builder = MessageBuilder::new();
+        builder
             .level(self.msg.level)
             .tag(&self.msg.tag)
             .content(rest)
             .date_time(datetime)
             .process_id(self.msg.pid)
-            .thread_id(self.msg.tid)
-            .build()?;
-        Ok(message)
+            .thread_id(self.msg.tid);
+        if let Some(uid) = self.msg.uid {
+            builder.uid(uid);
+        }
+        Ok(builder.build()?)
+    }
+}
+
+// Only the levels logcat prints, as a single uppercase letter. Filters also
+// accept lowercase, `?` and `S`.
+fn logcat_level(group: &str) -> Option<Level> {
+    let mut chars = group.chars();
+    let (Some(c), None) = (chars.next(), chars.next()) else {
+        return None;
+    };
+    if !c.is_ascii_uppercase() {
+        return None;
     }
+    Level::try_from(c)
+        .ok()
+        .filter(|level| (Level::Verbose..=Level::Fatal).contains(level))
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::parse::{Parser, ThreadTimeParser};
     use crate::{message::Level, parse};
     use chrono::{Datelike, Timelike};
 
@@ -307,6 +392,43 @@ mod tests {
         }
     }
 
+    #[test]
+    fn threadtime_modifiers() {
+        let msg =
+            parse::threadtime("2017-08-01 07:30:00.123456 +0100 10012  1234  1250 W tag: content")
+                .unwrap();
+        let date = msg.date().unwrap();
+        assert_eq!(date.year(), 2017);
+        assert_eq!(date.month(), 8);
+        assert_eq!(date.day(), 1);
+        assert_eq!(msg.time().unwrap().nanosecond(), 123_456_000);
+        assert_eq!(msg.uid(), Some(10012));
+        assert_eq!(msg.process_id(), Some(1234));
+        assert_eq!(msg.thread_id(), Some(1250));
+        assert_eq!(msg.level(), Level::Warning);
+        assert_eq!(msg.content(), "content");
+
+        let msg = parse::threadtime("08-01 07:30:00.123456789  root     1     1 I init: content")
+            .unwrap();
+        assert_eq!(msg.time().unwrap().nanosecond(), 123_456_789);
+        assert_eq!(msg.uid(), None);
+        assert_eq!(msg.process_id(), Some(1));
+    }
+
+    #[test]
+    fn threadtime_year() {
+        let mut parser = ThreadTimeParser::new();
+        parser.year(2017);
+        let msg = parser
+            .parse("08-01 07:30:00.000  1  1 I tag: content")
+            .unwrap();
+        assert_eq!(msg.date().unwrap().year(), 2017);
+        let msg = parser
+            .parse("2019-08-01 07:30:00.000  1  1 I tag: content")
+            .unwrap();
+        assert_eq!(msg.date().unwrap().year(), 2019);
+    }
+
     #[test]
     fn threadtime_malformed() {
         let cases = [
@@ -321,6 +443,10 @@ mod tests {
             "12-31 0:0:0.0 1 1  tag: content",
             "12-31 0:0:0.0 1 1  I: content",
             "12-31 0:0:0.0 1 1  I content",
+            "12-31 0:0:0.0 1 1 i tag: content",
+            "12-31 0:0:0.0 1 1 ? tag: content",
+            "12-31 0:0:0.0 1 1 S tag: content",
+            "12-31 0:0:0.0 1 1 Info tag: content",
         ];
 
         for case in &cases {
@@ -0,0 +1,279 @@
+//! Colorized rendering of messages for terminals, in the style of pidcat.
+//!
+//! Each message is rendered as a right-aligned tag column, a level badge and
+//! the content, wrapped and aligned under the first line. The tag is only
+//! shown when it differs from the previous message, and is colored by a hash
+//! of its name, so that a tag keeps its color across runs. Process starts and
+//! deaths seen by a [`ProcessTracker`] are shown as banners.
+//!
+//! # Examples
+//!
+//! ```
+//! use logcat::{parse, pretty::PrettyPrinter};
+//!
+//! let line = "08-01 07:30:00.123  1234  1250 W tag     : content";
+//! let msg = parse::threadtime(line).unwrap();
+//!
+//! let mut printer = PrettyPrinter::new();
+//! printer.color(false).tag_width(8);
+//! assert_eq!(printer.format(&msg), "     tag  W  content\n");
+//! ```
+
+use crate::analyze::{ProcessChange, ProcessTracker};
+use crate::message::{Level, Message};
+use std::env;
+use std::fmt::Write as _;
+use std::io::{self, IsTerminal};
+
+const RESET: &str = "\x1b[0m";
+const BOLD: &str = "\x1b[1m";
+
+// Foreground colors for tags, normal and bright.
+const TAG_COLORS: [u8; 12] = [31, 32, 33, 34, 35, 36, 91, 92, 93, 94, 95, 96];
+
+/// Renders messages for a terminal.
+#[derive(Debug)]
+pub struct PrettyPrinter {
+    color: bool,
+    width: Option<usize>,
+    tag_width: usize,
+    last_tag: Option<String>,
+    tracker: ProcessTracker,
+}
+
+impl PrettyPrinter {
+    /// Creates a new `PrettyPrinter` with colors, a tag column of 23
+    /// characters, and without wrapping.
+    pub fn new() -> PrettyPrinter {
+        PrettyPrinter {
+            color: true,
+            width: None,
+            tag_width: 23,
+            last_tag: None,
+            tracker: ProcessTracker::new(),
+        }
+    }
+
+    /// Creates a new `PrettyPrinter` for writing to `stream`.
+    ///
+    /// Colors are disabled if `stream` is not a terminal or the `NO_COLOR`
+    /// environment variable is set, and content is wrapped to the width in
+    /// the `COLUMNS` environment variable, if set.
+    pub fn for_stream<T: IsTerminal>(stream: &T) -> PrettyPrinter {
+        let mut printer = PrettyPrinter::new();
+        let terminal = stream.is_terminal();
+        printer.color(terminal && env::var_os("NO_COLOR").map_or(true, |value| value.is_empty()));
+        if terminal {
+            printer.width(
+                env::var("COLUMNS")
+                    .ok()
+                    .and_then(|columns| columns.parse().ok()),
+            );
+        }
+        printer
+    }
+
+    /// Sets whether to use ANSI colors.
+    pub fn color(&mut self, value: bool) -> &mut Self {
+        self.color = value;
+        self
+    }
+
+    /// Sets the width to wrap lines at, or `None` to not wrap.
+    pub fn width(&mut self, value: Option<usize>) -> &mut Self {
+        self.width = value;
+        self
+    }
+
+    /// Sets the width of the tag column. Longer tags are truncated.
+    pub fn tag_width(&mut self, value: usize) -> &mut Self {
+        self.tag_width = value;
+        self
+    }
+
+    /// Returns the rendered `message`, preceded by a banner if it starts or
+    /// ends a process, including the trailing newline.
+    pub fn format(&mut self, message: &Message) -> String {
+        let mut out = String::new();
+        if let Some(change) = self.tracker.track_change(message) {
+            self.render_banner(&mut out, &change);
+            self.last_tag = None;
+        }
+        self.render(&mut out, message);
+        out
+    }
+
+    /// Learns process starts and deaths from a message that is not
+    /// rendered, such as one that is filtered out, without showing a banner.
+    pub fn track(&mut self, message: &Message) {
+        self.tracker.track(message);
+    }
+
+    /// Writes the rendered `message`, as by [`format`](PrettyPrinter::format).
+    pub fn write<W: io::Write>(&mut self, out: &mut W, message: &Message) -> io::Result<()> {
+        out.write_all(self.format(message).as_bytes())
+    }
+
+    fn render_banner(&self, out: &mut String, change: &ProcessChange) {
+        let (lifetime, verb) = match change {
+            ProcessChange::Started(lifetime) => (lifetime, "started"),
+            ProcessChange::Ended(lifetime) => (lifetime, "ended"),
+        };
+        let text = format!(
+            "Process {} (PID {}) {}",
+            lifetime.name(),
+            lifetime.process_id(),
+            verb
+        );
+        let indent = " ".repeat(self.tag_width + 1);
+        if self.color {
+            let _ = writeln!(out, "\n{}{}{}{}\n", indent, BOLD, text, RESET);
+        } else {
+            let _ = writeln!(out, "\n{}{}\n", indent, text);
+        }
+    }
+
+    fn render(&mut self, out: &mut String, msg: &Message) {
+        let tag: String = msg.tag().chars().take(self.tag_width).collect();
+        if self.last_tag.as_deref() == Some(msg.tag()) {
+            out.push_str(&" ".repeat(self.tag_width));
+        } else {
+            let padded = format!("{:>1$}", tag, self.tag_width);
+            if self.color {
+                let _ = write!(out, "\x1b[{}m{}{}", tag_color(msg.tag()), padded, RESET);
+            } else {
+                out.push_str(&padded);
+            }
+            self.last_tag = Some(msg.tag().to_owned());
+        }
+
+        out.push(' ');
+        let badge = format!(" {} ", msg.level().short());
+        if self.color {
+            let (fg, bg) = level_colors(msg.level());
+            let _ = write!(out, "\x1b[{};{}m{}{}", fg, bg, badge, RESET);
+        } else {
+            out.push_str(&badge);
+        }
+        out.push(' ');
+
+        // The content starts after the tag column, the badge and two spaces.
+        let indent = self.tag_width + badge.len() + 2;
+        let width = self.width.map(|width| width.saturating_sub(indent).max(1));
+        let mut first = true;
+        for line in msg.content().lines() {
+            for chunk in wrap(line, width) {
+                if !first {
+                    out.push('\n');
+                    out.push_str(&" ".repeat(indent));
+                }
+                out.push_str(chunk);
+                first = false;
+            }
+        }
+        out.push('\n');
+    }
+}
+
+impl Default for PrettyPrinter {
+    fn default() -> PrettyPrinter {
+        PrettyPrinter::new()
+    }
+}
+
+// Returns the foreground color for `tag`, using FNV-1a as it does not vary
+// between runs.
+fn tag_color(tag: &str) -> u8 {
+    let hash = tag.bytes().fold(0x811c_9dc5u32, |hash, b| {
+        (hash ^ u32::from(b)).wrapping_mul(0x0100_0193)
+    });
+    TAG_COLORS[hash as usize % TAG_COLORS.len()]
+}
+
+// Returns the foreground and background colors for the badge of `level`.
+fn level_colors(level: Level) -> (u8, u8) {
+    match level {
+        Level::Verbose => (37, 40),
+        Level::Debug => (30, 44),
+        Level::Info => (30, 42),
+        Level::Warning => (30, 43),
+        Level::Error | Level::Fatal => (30, 41),
+        Level::Unknown | Level::Default | Level::Silent => (37, 100),
+    }
+}
+
+// Splits `line` into chunks of at most `width` characters.
+fn wrap(line: &str, width: Option<usize>) -> Vec<&str> {
+    let Some(width) = width else {
+        return vec![line];
+    };
+    let mut chunks = Vec::new();
+    let mut rest = line;
+    while rest.chars().count() > width {
+        let (end, _) = rest.char_indices().nth(width).expect("longer than width");
+        chunks.push(&rest[..end]);
+        rest = &rest[end..];
+    }
+    chunks.push(rest);
+    chunks
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parse;
+    use crate::pretty::{tag_color, PrettyPrinter};
+
+    #[test]
+    fn pretty() {
+        let data = "\
+01-02 10:00:00.000   567   600 I ActivityManager: Start proc 1234:com.example/u0a12 for activity {com.example/com.example.Main}
+01-02 10:00:01.000  1234  1234 I Example : started
+01-02 10:00:01.500  1234  1234 W Example : a long line that wraps
+01-02 10:00:02.000   567   600 I ActivityManager: Process com.example (pid 1234) has died";
+
+        let mut printer = PrettyPrinter::new();
+        printer.color(false).tag_width(8).width(Some(24));
+        let output: String = data
+            .lines()
+            .map(|line| printer.format(&parse::threadtime(line).unwrap()))
+            .collect();
+        assert_eq!(
+            output,
+            concat!(
+                "\n",
+                "         Process com.example (PID 1234) started\n",
+                "\n",
+                "Activity  I  Start proc \n",
+                "             1234:com.ex\n",
+                "             ample/u0a12\n",
+                "              for activi\n",
+                "             ty {com.exa\n",
+                "             mple/com.ex\n",
+                "             ample.Main}\n",
+                " Example  I  started\n",
+                "          W  a long line\n",
+                "              that wraps\n",
+                "\n",
+                "         Process com.example (PID 1234) ended\n",
+                "\n",
+                "Activity  I  Process com\n",
+                "             .example (p\n",
+                "             id 1234) ha\n",
+                "             s died\n",
+            )
+        );
+    }
+
+    #[test]
+    fn pretty_color() {
+        let msg = parse::threadtime("01-02 10:00:00.000  1  2 E tag     : one").unwrap();
+        let mut printer = PrettyPrinter::new();
+        printer.tag_width(4);
+        assert_eq!(
+            printer.format(&msg),
+            "\x1b[96m tag\x1b[0m \x1b[30;41m E \x1b[0m one\n"
+        );
+        assert_eq!(tag_color("tag"), 96);
+        assert_eq!(tag_color("ActivityManager"), 34);
+    }
+}
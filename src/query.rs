@@ -0,0 +1,244 @@
+//! A query language over message fields.
+//!
+//! A query combines conditions on message fields with `&&` (`and`), `||`
+//! (`or`), `!` (`not`) and parentheses, such as:
+//!
+//! ```text
+//! level>=W && tag~"^Net" && pid in (123, 456) && time in 10:00..10:05 && content contains "timeout"
+//! ```
+//!
+//! The fields are `level`, `tag`, `content`, `pid`, `tid`, `uid`, `buffer`
+//! and `time`. All fields support `==`, `!=` and `in (a, b, ...)`. `level`,
+//! `pid`, `tid`, `uid` and `time` also support `<`, `<=`, `>`, `>=` and
+//! the half-open range `in start..end`. `tag` and `content` also support the
+//! regular expression matches `~` and `!~`, and `contains`.
+//!
+//! Levels are written as letters or names, such as `W` or `warning`. Times
+//! are written as a time of day, such as `10:05` or `10:05:30.250`, or as a
+//! quoted date and time, such as `"2021-01-02 10:05:30"`. A condition on a
+//! field that is not available in a message is false.
+//!
+//! # Examples
+//!
+//! ```
+//! use logcat::{parse, query::Query};
+//!
+//! let query: Query = r#"level>=W && tag~"^Net""#.parse().unwrap();
+//!
+//! let source = "...";
+//! for line in source.lines() {
+//!     if let Ok(msg) = parse::threadtime(line) {
+//!         if query.matches(&msg) {
+//!             println!("{}", msg.content());
+//!         }
+//!     }
+//! }
+//! ```
+
+mod expr;
+mod lexer;
+mod parser;
+
+use crate::message::Message;
+use expr::Expr;
+use parser::Parser;
+use std::str::FromStr;
+use thiserror::Error;
+
+/// The error type for parsing a [`Query`].
+#[derive(Debug, Error, Eq, PartialEq)]
+pub enum Error {
+    /// The query contains a character that is not part of the language.
+    #[error("unexpected character: `{0}`")]
+    UnexpectedCharacter(char),
+
+    /// A quoted string is not closed.
+    #[error("unterminated string")]
+    UnterminatedString,
+
+    /// The query contains a token where it is not expected.
+    #[error("unexpected token: `{0}`")]
+    UnexpectedToken(String),
+
+    /// The query ended before it was complete.
+    #[error("unexpected end of query")]
+    UnexpectedEnd,
+
+    /// The query names an unknown field.
+    #[error("unknown field: `{0}`")]
+    UnknownField(String),
+
+    /// The operator is not supported by the field.
+    #[error("operator `{operator}` not supported by field `{field}`")]
+    InvalidOperator {
+        field: &'static str,
+        operator: String,
+    },
+
+    /// The value is not valid for the field.
+    #[error("invalid value for field `{field}`: `{value}`")]
+    InvalidValue { field: &'static str, value: String },
+
+    /// The regular expression is not valid.
+    #[error("invalid regular expression: {0}")]
+    InvalidRegex(String),
+}
+
+/// A parsed query.
+#[derive(Clone, Debug)]
+pub struct Query {
+    // `None` for an empty query.
+    expr: Option<Expr>,
+}
+
+impl Query {
+    /// Parses a query.
+    ///
+    /// An empty query matches all messages.
+    pub fn parse(query: &str) -> Result<Query, Error> {
+        let tokens = lexer::tokenize(query)?;
+        if tokens.is_empty() {
+            return Ok(Query { expr: None });
+        }
+
+        let expr = Parser::new(tokens).parse()?;
+        Ok(Query { expr: Some(expr) })
+    }
+
+    /// Returns `true` if `message` matches the query.
+    pub fn matches(&self, message: &Message) -> bool {
+        self.expr.as_ref().map_or(true, |expr| expr.eval(message))
+    }
+}
+
+impl FromStr for Query {
+    type Err = Error;
+
+    fn from_str(query: &str) -> Result<Query, Error> {
+        Query::parse(query)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        message::{Buffer, Level, MessageBuilder},
+        parse,
+        query::{Error, Query},
+    };
+
+    fn matches(query: &str, line: &str) -> bool {
+        let msg = parse::threadtime(line).unwrap();
+        Query::parse(query).unwrap().matches(&msg)
+    }
+
+    const LINE: &str = "01-02 10:03:00.500  123  456 W NetworkMonitor: probe timeout after 5s";
+
+    #[test]
+    fn query() {
+        assert!(matches(
+            r#"level>=W && tag~"^Net" && pid in (123, 456) && time in 10:00..10:05 && content contains "timeout""#,
+            LINE
+        ));
+        assert!(matches("", LINE));
+        assert!(matches("level == warning", LINE));
+        assert!(!matches("level > W", LINE));
+        assert!(matches("tag == NetworkMonitor and tid == 456", LINE));
+        assert!(matches("tag !~ Wifi", LINE));
+        assert!(matches(r#"content ~ "after \d+s$""#, LINE));
+        assert!(matches("pid in 100..200", LINE));
+        assert!(!matches("time in 10:03:00.500..10:03:00.500", LINE));
+        assert!(matches("time >= 10:03:00.500", LINE));
+    }
+
+    #[test]
+    fn query_precedence() {
+        assert!(matches(
+            "tag == A || tag == NetworkMonitor && pid == 123",
+            LINE
+        ));
+        assert!(!matches(
+            "(tag == A || tag == NetworkMonitor) && pid == 1",
+            LINE
+        ));
+        assert!(matches("!(pid == 1) && not tid == 1", LINE));
+    }
+
+    #[test]
+    fn query_missing_fields() {
+        assert!(!matches("uid == 10012", LINE));
+        assert!(!matches("uid != 10012", LINE));
+        assert!(!matches("buffer == main", LINE));
+
+        let msg = MessageBuilder::new()
+            .level(Level::Info)
+            .tag("tag")
+            .content("content")
+            .uid(10012)
+            .buffer(Buffer::System)
+            .build()
+            .unwrap();
+        let query = Query::parse("uid == 10012 && buffer in (main, system)").unwrap();
+        assert!(query.matches(&msg));
+        let query = Query::parse("time < 10:00").unwrap();
+        assert!(!query.matches(&msg));
+    }
+
+    #[test]
+    fn query_date_time() {
+        let msg = parse::threadtime(LINE).unwrap();
+        let year = msg.date().unwrap().format("%Y");
+        let query = format!(r#"time >= "{}-01-02 10:03""#, year);
+        assert!(Query::parse(&query).unwrap().matches(&msg));
+        let query = format!("time < {}-01-02T10:03", year);
+        assert!(!Query::parse(&query).unwrap().matches(&msg));
+    }
+
+    #[test]
+    fn query_malformed() {
+        let cases = [
+            ("level >=", Error::UnexpectedEnd),
+            ("level >= W &&", Error::UnexpectedEnd),
+            ("(level >= W", Error::UnexpectedEnd),
+            ("level >= W)", Error::UnexpectedToken(")".to_owned())),
+            ("name == x", Error::UnknownField("name".to_owned())),
+            (
+                "level == X",
+                Error::InvalidValue {
+                    field: "level",
+                    value: "X".to_owned(),
+                },
+            ),
+            (
+                "pid == abc",
+                Error::InvalidValue {
+                    field: "pid",
+                    value: "abc".to_owned(),
+                },
+            ),
+            (
+                "tag > x",
+                Error::InvalidOperator {
+                    field: "tag",
+                    operator: ">".to_owned(),
+                },
+            ),
+            (
+                "pid ~ 1",
+                Error::InvalidOperator {
+                    field: "pid",
+                    operator: "~".to_owned(),
+                },
+            ),
+            ("tag == x y", Error::UnexpectedToken("y".to_owned())),
+        ];
+
+        for (query, error) in cases {
+            assert_eq!(Query::parse(query).unwrap_err(), error, "{}", query);
+        }
+        assert!(matches!(
+            Query::parse("tag ~ \"(\""),
+            Err(Error::InvalidRegex(_))
+        ));
+    }
+}
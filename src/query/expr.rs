@@ -0,0 +1,135 @@
+use crate::message::{Buffer, Level, Message};
+use chrono::{NaiveDateTime, NaiveTime};
+use regex::Regex;
+use std::cmp::Ordering;
+
+#[derive(Clone, Debug)]
+pub(crate) enum Expr {
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Condition(Field, Test),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Field {
+    Level,
+    Tag,
+    Content,
+    Pid,
+    Tid,
+    Uid,
+    Buffer,
+    Time,
+}
+
+impl Field {
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Field::Level => "level",
+            Field::Tag => "tag",
+            Field::Content => "content",
+            Field::Pid => "pid",
+            Field::Tid => "tid",
+            Field::Uid => "uid",
+            Field::Buffer => "buffer",
+            Field::Time => "time",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum Value {
+    Level(Level),
+    Int(i64),
+    Str(String),
+    Buffer(Buffer),
+    TimeOfDay(NaiveTime),
+    DateTime(NaiveDateTime),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) enum Test {
+    Compare(Op, Value),
+    In(Vec<Value>),
+    // A half-open range, `start..end`.
+    Range(Value, Value),
+    Matches(Regex),
+    NotMatches(Regex),
+    Contains(String),
+}
+
+impl Expr {
+    pub(crate) fn eval(&self, msg: &Message) -> bool {
+        match self {
+            Expr::And(lhs, rhs) => lhs.eval(msg) && rhs.eval(msg),
+            Expr::Or(lhs, rhs) => lhs.eval(msg) || rhs.eval(msg),
+            Expr::Not(expr) => !expr.eval(msg),
+            Expr::Condition(field, test) => test.eval(*field, msg),
+        }
+    }
+}
+
+impl Test {
+    fn eval(&self, field: Field, msg: &Message) -> bool {
+        match self {
+            Test::Compare(op, value) => match compare(field, msg, value) {
+                Some(ordering) => match op {
+                    Op::Eq => ordering == Ordering::Equal,
+                    Op::Ne => ordering != Ordering::Equal,
+                    Op::Lt => ordering == Ordering::Less,
+                    Op::Le => ordering != Ordering::Greater,
+                    Op::Gt => ordering == Ordering::Greater,
+                    Op::Ge => ordering != Ordering::Less,
+                },
+                None => false,
+            },
+            Test::In(values) => values
+                .iter()
+                .any(|value| compare(field, msg, value) == Some(Ordering::Equal)),
+            Test::Range(start, end) => {
+                compare(field, msg, start).is_some_and(|o| o != Ordering::Less)
+                    && compare(field, msg, end) == Some(Ordering::Less)
+            }
+            Test::Matches(regex) => regex.is_match(text(field, msg)),
+            Test::NotMatches(regex) => !regex.is_match(text(field, msg)),
+            Test::Contains(value) => text(field, msg).contains(value.as_str()),
+        }
+    }
+}
+
+fn text(field: Field, msg: &Message) -> &str {
+    match field {
+        Field::Content => msg.content(),
+        _ => msg.tag(),
+    }
+}
+
+/// Compares the `field` of `msg` to `value`.
+///
+/// Returns `None` if the field is not available.
+fn compare(field: Field, msg: &Message, value: &Value) -> Option<Ordering> {
+    let ordering = match (field, value) {
+        (Field::Level, Value::Level(level)) => msg.level().cmp(level),
+        (Field::Tag, Value::Str(value)) => msg.tag().cmp(value.as_str()),
+        (Field::Content, Value::Str(value)) => msg.content().cmp(value.as_str()),
+        (Field::Pid, Value::Int(value)) => i64::from(msg.process_id()?).cmp(value),
+        (Field::Tid, Value::Int(value)) => i64::from(msg.thread_id()?).cmp(value),
+        (Field::Uid, Value::Int(value)) => i64::from(msg.uid()?).cmp(value),
+        (Field::Buffer, Value::Buffer(value)) => (msg.buffer()? as u8).cmp(&(*value as u8)),
+        (Field::Time, Value::TimeOfDay(value)) => msg.time()?.cmp(value),
+        (Field::Time, Value::DateTime(value)) => msg.date_time()?.cmp(value),
+        _ => return None,
+    };
+    Some(ordering)
+}
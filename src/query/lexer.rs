@@ -0,0 +1,198 @@
+use crate::query::Error;
+use std::fmt;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum Token {
+    /// A bare word, such as a field name, keyword, number or level.
+    Word(String),
+    /// A quoted string.
+    Str(String),
+    And,
+    Or,
+    Not,
+    LParen,
+    RParen,
+    Comma,
+    Range,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Match,
+    NotMatch,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let symbol = match self {
+            Token::Word(word) => return f.write_str(word),
+            Token::Str(value) => return write!(f, "\"{}\"", value),
+            Token::And => "&&",
+            Token::Or => "||",
+            Token::Not => "!",
+            Token::LParen => "(",
+            Token::RParen => ")",
+            Token::Comma => ",",
+            Token::Range => "..",
+            Token::Eq => "==",
+            Token::Ne => "!=",
+            Token::Lt => "<",
+            Token::Le => "<=",
+            Token::Gt => ">",
+            Token::Ge => ">=",
+            Token::Match => "~",
+            Token::NotMatch => "!~",
+        };
+        f.write_str(symbol)
+    }
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '.' | ':' | '-' | '/' | '$' | '*')
+}
+
+/// Splits a query into tokens.
+pub(crate) fn tokenize(query: &str) -> Result<Vec<Token>, Error> {
+    let mut tokens = Vec::new();
+    let mut rest = query;
+
+    while let Some(c) = rest.chars().next() {
+        if c.is_whitespace() {
+            rest = &rest[c.len_utf8()..];
+            continue;
+        }
+
+        const SYMBOLS: [(&str, Token); 16] = [
+            ("&&", Token::And),
+            ("||", Token::Or),
+            ("==", Token::Eq),
+            ("!=", Token::Ne),
+            ("!~", Token::NotMatch),
+            ("<=", Token::Le),
+            (">=", Token::Ge),
+            ("..", Token::Range),
+            ("!", Token::Not),
+            ("(", Token::LParen),
+            (")", Token::RParen),
+            (",", Token::Comma),
+            ("=", Token::Eq),
+            ("<", Token::Lt),
+            (">", Token::Gt),
+            ("~", Token::Match),
+        ];
+        if let Some((symbol, token)) = SYMBOLS.iter().find(|(s, _)| rest.starts_with(s)) {
+            tokens.push(token.clone());
+            rest = &rest[symbol.len()..];
+            continue;
+        }
+        if c == '"' {
+            let (value, r) = string(&rest[1..])?;
+            tokens.push(Token::Str(value));
+            rest = r;
+            continue;
+        }
+
+        if is_word_char(c) {
+            // Words end at `..` so that `10:00..10:05` is a range.
+            let mut end = rest.len();
+            for (i, c) in rest.char_indices() {
+                if !is_word_char(c) || rest[i..].starts_with("..") {
+                    end = i;
+                    break;
+                }
+            }
+            tokens.push(Token::Word(rest[..end].to_owned()));
+            rest = &rest[end..];
+            continue;
+        }
+
+        return Err(Error::UnexpectedCharacter(c));
+    }
+
+    Ok(tokens)
+}
+
+/// Reads a string after its opening quote, returning the string and the
+/// text after its closing quote.
+fn string(rest: &str) -> Result<(String, &str), Error> {
+    let mut value = String::new();
+    let mut chars = rest.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' => return Ok((value, &rest[i + 1..])),
+            '\\' => match chars.next() {
+                Some((_, '"')) => value.push('"'),
+                Some((_, '\\')) => value.push('\\'),
+                // Keep other escapes, such as `\d` in regular expressions.
+                Some((_, c)) => {
+                    value.push('\\');
+                    value.push(c);
+                }
+                None => break,
+            },
+            c => value.push(c),
+        }
+    }
+    Err(Error::UnterminatedString)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::query::{
+        lexer::{tokenize, Token},
+        Error,
+    };
+
+    fn word(s: &str) -> Token {
+        Token::Word(s.to_owned())
+    }
+
+    #[test]
+    fn tokens() {
+        assert_eq!(
+            tokenize(r#"level>=W && tag~"^Net\"\d" || !(pid in (1, 2))"#).unwrap(),
+            [
+                word("level"),
+                Token::Ge,
+                word("W"),
+                Token::And,
+                word("tag"),
+                Token::Match,
+                Token::Str(r#"^Net"\d"#.to_owned()),
+                Token::Or,
+                Token::Not,
+                Token::LParen,
+                word("pid"),
+                word("in"),
+                Token::LParen,
+                word("1"),
+                Token::Comma,
+                word("2"),
+                Token::RParen,
+                Token::RParen,
+            ]
+        );
+    }
+
+    #[test]
+    fn tokens_range() {
+        assert_eq!(
+            tokenize("time in 10:00..10:05:30.5").unwrap(),
+            [
+                word("time"),
+                word("in"),
+                word("10:00"),
+                Token::Range,
+                word("10:05:30.5"),
+            ]
+        );
+    }
+
+    #[test]
+    fn tokens_malformed() {
+        assert_eq!(tokenize("tag == \"x"), Err(Error::UnterminatedString));
+        assert_eq!(tokenize("tag == #"), Err(Error::UnexpectedCharacter('#')));
+    }
+}
@@ -0,0 +1,231 @@
+use crate::message::{Buffer, Level};
+use crate::query::{
+    expr::{Expr, Field, Op, Test, Value},
+    lexer::Token,
+    Error,
+};
+use chrono::{NaiveDateTime, NaiveTime};
+use regex::Regex;
+
+pub(crate) struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    pub(crate) fn new(tokens: Vec<Token>) -> Parser {
+        Parser { tokens, pos: 0 }
+    }
+
+    /// Parses all tokens into an expression.
+    pub(crate) fn parse(mut self) -> Result<Expr, Error> {
+        let expr = self.parse_or()?;
+        match self.next() {
+            Some(token) => Err(Error::UnexpectedToken(token.to_string())),
+            None => Ok(expr),
+        }
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn expect(&mut self, expected: Token) -> Result<(), Error> {
+        match self.next() {
+            Some(token) if token == expected => Ok(()),
+            Some(token) => Err(Error::UnexpectedToken(token.to_string())),
+            None => Err(Error::UnexpectedEnd),
+        }
+    }
+
+    fn is_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Some(Token::Word(word)) if word.eq_ignore_ascii_case(keyword))
+    }
+
+    // or := and ( ("||" | "or") and )*
+    fn parse_or(&mut self) -> Result<Expr, Error> {
+        let mut expr = self.parse_and()?;
+        while self.peek() == Some(&Token::Or) || self.is_keyword("or") {
+            self.next();
+            expr = Expr::Or(Box::new(expr), Box::new(self.parse_and()?));
+        }
+        Ok(expr)
+    }
+
+    // and := unary ( ("&&" | "and") unary )*
+    fn parse_and(&mut self) -> Result<Expr, Error> {
+        let mut expr = self.parse_unary()?;
+        while self.peek() == Some(&Token::And) || self.is_keyword("and") {
+            self.next();
+            expr = Expr::And(Box::new(expr), Box::new(self.parse_unary()?));
+        }
+        Ok(expr)
+    }
+
+    // unary := ("!" | "not") unary | "(" or ")" | condition
+    fn parse_unary(&mut self) -> Result<Expr, Error> {
+        if self.peek() == Some(&Token::Not) || self.is_keyword("not") {
+            self.next();
+            return Ok(Expr::Not(Box::new(self.parse_unary()?)));
+        }
+
+        if self.peek() == Some(&Token::LParen) {
+            self.next();
+            let expr = self.parse_or()?;
+            self.expect(Token::RParen)?;
+            return Ok(expr);
+        }
+
+        self.parse_condition()
+    }
+
+    // condition := field op value
+    //            | field ("~" | "!~" | "contains") string
+    //            | field "in" "(" value ("," value)* ")"
+    //            | field "in" value ".." value
+    fn parse_condition(&mut self) -> Result<Expr, Error> {
+        let field = match self.next() {
+            Some(Token::Word(word)) => parse_field(&word)?,
+            Some(token) => return Err(Error::UnexpectedToken(token.to_string())),
+            None => return Err(Error::UnexpectedEnd),
+        };
+
+        let test = match self.next() {
+            Some(Token::Eq) => Test::Compare(Op::Eq, self.parse_value(field)?),
+            Some(Token::Ne) => Test::Compare(Op::Ne, self.parse_value(field)?),
+            Some(token @ (Token::Lt | Token::Le | Token::Gt | Token::Ge)) => {
+                check_ordered(field, &token)?;
+                let op = match token {
+                    Token::Lt => Op::Lt,
+                    Token::Le => Op::Le,
+                    Token::Gt => Op::Gt,
+                    _ => Op::Ge,
+                };
+                Test::Compare(op, self.parse_value(field)?)
+            }
+            Some(token @ (Token::Match | Token::NotMatch)) => {
+                check_text(field, &token)?;
+                let pattern = self.parse_text()?;
+                let regex = Regex::new(&pattern).map_err(|e| Error::InvalidRegex(e.to_string()))?;
+                match token {
+                    Token::Match => Test::Matches(regex),
+                    _ => Test::NotMatches(regex),
+                }
+            }
+            Some(Token::Word(word)) if word.eq_ignore_ascii_case("contains") => {
+                check_text(field, &Token::Word(word))?;
+                Test::Contains(self.parse_text()?)
+            }
+            Some(Token::Word(word)) if word.eq_ignore_ascii_case("in") => {
+                if self.peek() == Some(&Token::LParen) {
+                    self.next();
+                    let mut values = vec![self.parse_value(field)?];
+                    while self.peek() == Some(&Token::Comma) {
+                        self.next();
+                        values.push(self.parse_value(field)?);
+                    }
+                    self.expect(Token::RParen)?;
+                    Test::In(values)
+                } else {
+                    check_ordered(field, &Token::Range)?;
+                    let start = self.parse_value(field)?;
+                    self.expect(Token::Range)?;
+                    let end = self.parse_value(field)?;
+                    Test::Range(start, end)
+                }
+            }
+            Some(token) => return Err(Error::UnexpectedToken(token.to_string())),
+            None => return Err(Error::UnexpectedEnd),
+        };
+
+        Ok(Expr::Condition(field, test))
+    }
+
+    fn parse_text(&mut self) -> Result<String, Error> {
+        match self.next() {
+            Some(Token::Word(value) | Token::Str(value)) => Ok(value),
+            Some(token) => Err(Error::UnexpectedToken(token.to_string())),
+            None => Err(Error::UnexpectedEnd),
+        }
+    }
+
+    fn parse_value(&mut self, field: Field) -> Result<Value, Error> {
+        let text = self.parse_text()?;
+        let invalid = || Error::InvalidValue {
+            field: field.name(),
+            value: text.clone(),
+        };
+
+        let value = match field {
+            Field::Level => Value::Level(text.parse::<Level>().map_err(|_| invalid())?),
+            Field::Tag | Field::Content => Value::Str(text.clone()),
+            Field::Pid | Field::Tid | Field::Uid => {
+                Value::Int(text.parse().map_err(|_| invalid())?)
+            }
+            Field::Buffer => Value::Buffer(text.parse::<Buffer>().map_err(|_| invalid())?),
+            Field::Time => parse_time(&text).ok_or_else(invalid)?,
+        };
+        Ok(value)
+    }
+}
+
+fn parse_field(word: &str) -> Result<Field, Error> {
+    let field = match word.to_ascii_lowercase().as_str() {
+        "level" => Field::Level,
+        "tag" => Field::Tag,
+        "content" | "message" => Field::Content,
+        "pid" => Field::Pid,
+        "tid" => Field::Tid,
+        "uid" => Field::Uid,
+        "buffer" => Field::Buffer,
+        "time" => Field::Time,
+        _ => return Err(Error::UnknownField(word.to_owned())),
+    };
+    Ok(field)
+}
+
+fn check_ordered(field: Field, token: &Token) -> Result<(), Error> {
+    match field {
+        Field::Level | Field::Pid | Field::Tid | Field::Uid | Field::Time => Ok(()),
+        _ => Err(Error::InvalidOperator {
+            field: field.name(),
+            operator: token.to_string(),
+        }),
+    }
+}
+
+fn check_text(field: Field, token: &Token) -> Result<(), Error> {
+    match field {
+        Field::Tag | Field::Content => Ok(()),
+        _ => Err(Error::InvalidOperator {
+            field: field.name(),
+            operator: token.to_string(),
+        }),
+    }
+}
+
+// 10:00, 10:00:30, 10:00:30.500, 2021-01-02 10:00:30.500, 2021-01-02T10:00
+fn parse_time(text: &str) -> Option<Value> {
+    for format in ["%H:%M:%S%.f", "%H:%M"] {
+        if let Ok(time) = NaiveTime::parse_from_str(text, format) {
+            return Some(Value::TimeOfDay(time));
+        }
+    }
+    for format in [
+        "%Y-%m-%d %H:%M:%S%.f",
+        "%Y-%m-%d %H:%M",
+        "%Y-%m-%dT%H:%M:%S%.f",
+        "%Y-%m-%dT%H:%M",
+    ] {
+        if let Ok(date_time) = NaiveDateTime::parse_from_str(text, format) {
+            return Some(Value::DateTime(date_time));
+        }
+    }
+    None
+}
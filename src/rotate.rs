@@ -0,0 +1,251 @@
+//! Reading the files written by `logcat -f` with rotation.
+//!
+//! `logcat -f /data/log/logcat -r 1024 -n 8` writes to `logcat`, and rotates
+//! it to `logcat.1`, `logcat.1` to `logcat.2` and so on, up to `logcat.8`.
+//! A [`RotatedReader`] reads such a set from the oldest file to the newest
+//! as a single stream of messages.
+//!
+//! Messages repeated at the start of a file, such as when the set was
+//! assembled from overlapping captures, are skipped: the longest run of
+//! messages at the end of the previous file that the next file starts with
+//! is only read once. Compressed files, such as `logcat.1.gz`, are
+//! decompressed as by [`decompress::open`].
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use logcat::{parse::ThreadTimeParser, rotate::RotatedReader};
+//!
+//! let reader = RotatedReader::open("/data/log/logcat", ThreadTimeParser::new()).unwrap();
+//! for msg in reader {
+//!     println!("{}", msg.unwrap().content());
+//! }
+//! ```
+
+use crate::decompress;
+use crate::message::Message;
+use crate::parse::{MessageIterator, Parser};
+use std::collections::VecDeque;
+use std::fs;
+use std::io::{self, BufRead};
+use std::path::{Path, PathBuf};
+
+/// Returns the files of the rotated set of `path`, oldest first.
+///
+/// These are the files named as `path`, or as `path` followed by a dot and a
+/// number, in the same directory, optionally followed by `.gz`, `.zst` or
+/// `.xz`.
+pub fn discover<T: AsRef<Path>>(path: T) -> io::Result<Vec<PathBuf>> {
+    let path = path.as_ref();
+    let name = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "invalid file name"))?;
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+
+    let mut files = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let file_name = entry.file_name();
+        let Some(rest) = file_name
+            .to_str()
+            .and_then(|file_name| file_name.strip_prefix(name))
+        else {
+            continue;
+        };
+        let rest = [".gz", ".zst", ".xz"]
+            .iter()
+            .find_map(|suffix| rest.strip_suffix(suffix))
+            .unwrap_or(rest);
+        let number = if rest.is_empty() {
+            0
+        } else {
+            match rest.strip_prefix('.').map(str::parse::<u32>) {
+                Some(Ok(number)) => number,
+                _ => continue,
+            }
+        };
+        files.push((number, entry.path()));
+    }
+    if files.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("no log files found for {}", path.display()),
+        ));
+    }
+
+    files.sort_by_key(|(number, _)| std::cmp::Reverse(*number));
+    Ok(files.into_iter().map(|(_, path)| path).collect())
+}
+
+/// Reads the messages of a rotated set of files as one stream.
+pub struct RotatedReader<P> {
+    paths: VecDeque<PathBuf>,
+    parser: Option<P>,
+    current: Option<MessageIterator<Box<dyn BufRead + Send>, P>>,
+    overlap: usize,
+    // The messages read ahead, up to `overlap` more than returned, so that
+    // the end of a file can be compared with the start of the next one.
+    buffer: VecDeque<Message>,
+}
+
+impl<P: Parser> RotatedReader<P> {
+    /// Creates a new `RotatedReader` for the rotated set of `path`, as found
+    /// by [`discover`].
+    pub fn open<T: AsRef<Path>>(path: T, parser: P) -> io::Result<RotatedReader<P>> {
+        Ok(RotatedReader::new(discover(path)?, parser))
+    }
+
+    /// Creates a new `RotatedReader` for `paths`, oldest first.
+    pub fn new(paths: Vec<PathBuf>, parser: P) -> RotatedReader<P> {
+        RotatedReader {
+            paths: paths.into(),
+            parser: Some(parser),
+            current: None,
+            overlap: 1000,
+            buffer: VecDeque::new(),
+        }
+    }
+
+    /// Sets the largest number of messages that can be repeated at the
+    /// start of a file, 1000 by default.
+    ///
+    /// As many messages are read ahead of those returned.
+    pub fn overlap(&mut self, value: usize) -> &mut Self {
+        self.overlap = value;
+        self
+    }
+
+    fn open_next(&mut self, path: &Path) -> io::Result<()> {
+        let input = decompress::open(path)?;
+        let parser = self.parser.take().expect("parser is not in use");
+        let mut iter = MessageIterator::new(input, parser);
+
+        let mut head = Vec::new();
+        while head.len() < self.overlap {
+            match iter.next() {
+                Some(Ok(msg)) => head.push(msg),
+                Some(Err(e)) => {
+                    self.buffer.extend(head);
+                    self.current = Some(iter);
+                    return Err(e);
+                }
+                None => break,
+            }
+        }
+        let repeated = overlapping(&self.buffer, &head);
+        self.buffer.extend(head.into_iter().skip(repeated));
+        self.current = Some(iter);
+        Ok(())
+    }
+}
+
+impl<P: Parser> Iterator for RotatedReader<P> {
+    type Item = io::Result<Message>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.buffer.len() <= self.overlap {
+            if let Some(current) = &mut self.current {
+                match current.next() {
+                    Some(Ok(msg)) => {
+                        self.buffer.push_back(msg);
+                        continue;
+                    }
+                    Some(Err(e)) => return Some(Err(e)),
+                    None => {
+                        let current = self.current.take().expect("a file is open");
+                        self.parser = Some(current.into_parser());
+                    }
+                }
+            }
+
+            let Some(path) = self.paths.pop_front() else {
+                break;
+            };
+            if let Err(e) = self.open_next(&path) {
+                return Some(Err(e));
+            }
+        }
+        self.buffer.pop_front().map(Ok)
+    }
+}
+
+// Returns the length of the longest run at the end of `tail` that `head`
+// starts with.
+fn overlapping(tail: &VecDeque<Message>, head: &[Message]) -> usize {
+    (1..=tail.len().min(head.len()))
+        .rev()
+        .find(|len| tail.range(tail.len() - len..).eq(&head[..*len]))
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parse::ThreadTimeParser;
+    use crate::rotate::{discover, RotatedReader};
+    use std::fs;
+
+    #[test]
+    fn rotated() {
+        let dir = std::env::temp_dir().join(format!("logcat-rotate-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let line = |second: u32, content: &str| {
+            format!(
+                "08-01 07:30:{:02}.000  1234  1250 I tag: {}\n",
+                second, content
+            )
+        };
+
+        let files = [
+            ("logcat.10", vec![line(0, "zero"), line(1, "one")]),
+            (
+                "logcat.2.gz",
+                vec![line(1, "one"), line(2, "two"), line(3, "three")],
+            ),
+            ("logcat.1", vec![line(2, "two"), line(3, "three")]),
+            (
+                "logcat",
+                vec![
+                    "--------- beginning of main\n".to_owned(),
+                    line(3, "three"),
+                    line(3, "three"),
+                    line(4, "four"),
+                ],
+            ),
+            ("logcat.old", vec![line(9, "ignored")]),
+            ("logcat.old.gz", vec![line(9, "ignored")]),
+            ("other", vec![line(9, "ignored")]),
+        ];
+        for (name, lines) in &files {
+            fs::write(dir.join(name), lines.concat()).unwrap();
+        }
+
+        let paths = discover(dir.join("logcat")).unwrap();
+        let names: Vec<_> = paths
+            .iter()
+            .map(|path| path.file_name().unwrap().to_str().unwrap())
+            .collect();
+        assert_eq!(names, ["logcat.10", "logcat.2.gz", "logcat.1", "logcat"]);
+
+        let contents = |reader: RotatedReader<ThreadTimeParser>| -> Vec<String> {
+            reader
+                .map(|msg| msg.unwrap().content().to_owned())
+                .collect()
+        };
+        let reader = RotatedReader::open(dir.join("logcat"), ThreadTimeParser::new()).unwrap();
+        assert_eq!(
+            contents(reader),
+            ["zero", "one", "two", "three", "three", "four"]
+        );
+
+        let mut reader = RotatedReader::new(paths, ThreadTimeParser::new());
+        reader.overlap(0);
+        assert_eq!(contents(reader).len(), 10);
+
+        assert!(discover(dir.join("missing")).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
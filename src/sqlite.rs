@@ -0,0 +1,410 @@
+//! Exporting messages into a SQLite database.
+//!
+//! The database has the following schema:
+//!
+//! ```sql
+//! CREATE TABLE tags (
+//!     id INTEGER PRIMARY KEY,
+//!     name TEXT NOT NULL UNIQUE
+//! );
+//! CREATE TABLE processes (
+//!     id INTEGER PRIMARY KEY,
+//!     pid INTEGER NOT NULL,
+//!     name TEXT NOT NULL,
+//!     UNIQUE (pid, name)
+//! );
+//! CREATE TABLE messages (
+//!     id INTEGER PRIMARY KEY,
+//!     time TEXT,             -- 2017-08-01 07:30:00.123000000
+//!     level INTEGER NOT NULL, -- Android log priority, such as 5 for W
+//!     tag_id INTEGER NOT NULL REFERENCES tags (id),
+//!     pid INTEGER,
+//!     tid INTEGER,
+//!     uid INTEGER,
+//!     buffer TEXT,
+//!     process_id INTEGER REFERENCES processes (id),
+//!     content TEXT NOT NULL
+//! );
+//! CREATE VIRTUAL TABLE messages_fts USING fts5 (content, ...);
+//! CREATE VIEW messages_view AS SELECT ...;
+//! ```
+//!
+//! Times are stored with nanoseconds, always with nine digits, so they sort
+//! as text.
+//!
+//! `messages` is indexed on `time`, `tag_id`, `pid` and `level`, and
+//! `messages_fts` is a full-text index of `content`, joined by `rowid`.
+//! `messages_view` joins the tag and process names into each message.
+//! Processes are named from the written messages, see [`ProcessTracker`].
+//!
+//! # Examples
+//!
+//! ```
+//! use logcat::{parse, sqlite::Writer};
+//!
+//! let line = "08-01 07:30:00.123  1234  1250 W tag     : Out of memory";
+//! let msg = parse::threadtime(line).unwrap();
+//!
+//! let mut writer = Writer::open_in_memory().unwrap();
+//! writer.write(&msg).unwrap();
+//! let conn = writer.finish().unwrap();
+//!
+//! let count: i64 = conn
+//!     .query_row(
+//!         "SELECT count(*) FROM messages_fts WHERE messages_fts MATCH 'memory'",
+//!         [],
+//!         |row| row.get(0),
+//!     )
+//!     .unwrap();
+//! assert_eq!(count, 1);
+//! ```
+
+use crate::analyze::ProcessTracker;
+use crate::message::Message;
+use rusqlite::{params, Connection};
+use std::collections::HashMap;
+use std::path::Path;
+use thiserror::Error;
+
+pub use rusqlite;
+
+const TIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S%.9f";
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS tags (
+    id INTEGER PRIMARY KEY,
+    name TEXT NOT NULL UNIQUE
+);
+CREATE TABLE IF NOT EXISTS processes (
+    id INTEGER PRIMARY KEY,
+    pid INTEGER NOT NULL,
+    name TEXT NOT NULL,
+    UNIQUE (pid, name)
+);
+CREATE TABLE IF NOT EXISTS messages (
+    id INTEGER PRIMARY KEY,
+    time TEXT,
+    level INTEGER NOT NULL,
+    tag_id INTEGER NOT NULL REFERENCES tags (id),
+    pid INTEGER,
+    tid INTEGER,
+    uid INTEGER,
+    buffer TEXT,
+    process_id INTEGER REFERENCES processes (id),
+    content TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS messages_time ON messages (time);
+CREATE INDEX IF NOT EXISTS messages_tag_id ON messages (tag_id);
+CREATE INDEX IF NOT EXISTS messages_pid ON messages (pid);
+CREATE INDEX IF NOT EXISTS messages_level ON messages (level);
+CREATE VIRTUAL TABLE IF NOT EXISTS messages_fts USING fts5 (
+    content,
+    content = 'messages',
+    content_rowid = 'id'
+);
+CREATE VIEW IF NOT EXISTS messages_view AS
+SELECT messages.id, time, level, tags.name AS tag, messages.pid, tid, uid,
+       buffer, processes.name AS process, content
+FROM messages
+JOIN tags ON tags.id = messages.tag_id
+LEFT JOIN processes ON processes.id = messages.process_id;
+";
+
+/// The error type for exporting to SQLite.
+#[derive(Debug, Error)]
+pub enum Error {
+    /// A SQLite operation failed.
+    #[error("sqlite: {0}")]
+    Sqlite(#[from] rusqlite::Error),
+}
+
+/// Writes messages into a SQLite database.
+///
+/// Messages are inserted in transactions of [`batch_size`](Writer::batch_size)
+/// messages. The last transaction is committed by [`finish`](Writer::finish),
+/// or when the `Writer` is dropped, ignoring errors.
+pub struct Writer {
+    // Only `None` once finished.
+    conn: Option<Connection>,
+    tags: HashMap<String, i64>,
+    processes: HashMap<(i32, String), i64>,
+    tracker: ProcessTracker,
+    batch_size: usize,
+    // Whether a transaction is open, even if no message was inserted in it.
+    in_transaction: bool,
+    pending: usize,
+}
+
+impl Writer {
+    /// Opens or creates the database at `path` and creates the schema.
+    ///
+    /// To speed up inserting large logs, writes are never synced to disk and
+    /// the journal is kept in memory, so a crash while writing can corrupt
+    /// the database.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Writer, Error> {
+        let conn = Connection::open(path)?;
+        conn.execute_batch("PRAGMA synchronous = OFF; PRAGMA journal_mode = MEMORY;")?;
+        Writer::new(conn)
+    }
+
+    /// Creates a new in-memory database with the schema.
+    pub fn open_in_memory() -> Result<Writer, Error> {
+        Writer::new(Connection::open_in_memory()?)
+    }
+
+    /// Creates a new `Writer` writing to `conn`, creating the schema if it
+    /// does not exist yet.
+    pub fn new(conn: Connection) -> Result<Writer, Error> {
+        conn.execute_batch(SCHEMA)?;
+
+        let mut tags = HashMap::new();
+        {
+            let mut stmt = conn.prepare("SELECT id, name FROM tags")?;
+            let rows = stmt.query_map([], |row| Ok((row.get(1)?, row.get(0)?)))?;
+            for row in rows {
+                let (name, id) = row?;
+                tags.insert(name, id);
+            }
+        }
+        let mut processes = HashMap::new();
+        {
+            let mut stmt = conn.prepare("SELECT id, pid, name FROM processes")?;
+            let rows = stmt.query_map([], |row| Ok(((row.get(1)?, row.get(2)?), row.get(0)?)))?;
+            for row in rows {
+                let (key, id) = row?;
+                processes.insert(key, id);
+            }
+        }
+
+        Ok(Writer {
+            conn: Some(conn),
+            tags,
+            processes,
+            tracker: ProcessTracker::new(),
+            batch_size: 100_000,
+            in_transaction: false,
+            pending: 0,
+        })
+    }
+
+    /// Sets the number of messages inserted per transaction.
+    pub fn batch_size(&mut self, value: usize) -> &mut Self {
+        self.batch_size = value.max(1);
+        self
+    }
+
+    /// Inserts one message.
+    pub fn write(&mut self, msg: &Message) -> Result<(), Error> {
+        if !self.in_transaction {
+            self.conn().execute_batch("BEGIN")?;
+            self.in_transaction = true;
+        }
+
+        self.tracker.track(msg);
+        let tag_id = self.tag_id(msg.tag())?;
+        let process_id = match (msg.process_id(), self.tracker.process_name_for(msg)) {
+            (Some(pid), Some(name)) => Some(self.process_id(pid, name.to_owned())?),
+            _ => None,
+        };
+
+        let conn = self.conn();
+        conn.prepare_cached(
+            "INSERT INTO messages
+                 (time, level, tag_id, pid, tid, uid, buffer, process_id, content)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+        )?
+        .execute(params![
+            msg.date_time()
+                .map(|date_time| date_time.format(TIME_FORMAT).to_string()),
+            msg.level().priority(),
+            tag_id,
+            msg.process_id(),
+            msg.thread_id(),
+            msg.uid(),
+            msg.buffer().map(|buffer| buffer.name()),
+            process_id,
+            msg.content(),
+        ])?;
+        let id = conn.last_insert_rowid();
+        conn.prepare_cached("INSERT INTO messages_fts (rowid, content) VALUES (?1, ?2)")?
+            .execute(params![id, msg.content()])?;
+
+        self.pending += 1;
+        if self.pending >= self.batch_size {
+            self.commit()?;
+        }
+        Ok(())
+    }
+
+    /// Inserts all `messages`.
+    pub fn write_all<I: IntoIterator<Item = Message>>(&mut self, messages: I) -> Result<(), Error> {
+        for msg in messages {
+            self.write(&msg)?;
+        }
+        Ok(())
+    }
+
+    /// Commits the inserted messages.
+    pub fn commit(&mut self) -> Result<(), Error> {
+        if self.in_transaction {
+            self.conn().execute_batch("COMMIT")?;
+            self.in_transaction = false;
+            self.pending = 0;
+        }
+        Ok(())
+    }
+
+    /// Commits the inserted messages and returns the connection, such as for
+    /// running queries.
+    pub fn finish(mut self) -> Result<Connection, Error> {
+        self.commit()?;
+        Ok(self.conn.take().expect("not finished"))
+    }
+
+    fn conn(&self) -> &Connection {
+        self.conn.as_ref().expect("not finished")
+    }
+
+    fn tag_id(&mut self, tag: &str) -> Result<i64, Error> {
+        if let Some(id) = self.tags.get(tag) {
+            return Ok(*id);
+        }
+        let conn = self.conn();
+        conn.prepare_cached("INSERT INTO tags (name) VALUES (?1)")?
+            .execute([tag])?;
+        let id = conn.last_insert_rowid();
+        self.tags.insert(tag.to_owned(), id);
+        Ok(id)
+    }
+
+    fn process_id(&mut self, pid: i32, name: String) -> Result<i64, Error> {
+        let key = (pid, name);
+        if let Some(id) = self.processes.get(&key) {
+            return Ok(*id);
+        }
+        let conn = self.conn();
+        conn.prepare_cached("INSERT INTO processes (pid, name) VALUES (?1, ?2)")?
+            .execute(params![key.0, key.1])?;
+        let id = conn.last_insert_rowid();
+        self.processes.insert(key, id);
+        Ok(id)
+    }
+}
+
+impl Drop for Writer {
+    fn drop(&mut self) {
+        let _ = self.commit();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parse;
+    use crate::sqlite::Writer;
+    use rusqlite::Connection;
+    use std::fs;
+
+    #[test]
+    fn sqlite() {
+        let data = "\
+2017-08-01 07:30:00.123  1000  1020 I ActivityManager: Start proc 1234:com.example/u0a12 for activity com.example/.Main
+2017-08-01 07:30:01.000  1234  1250 W Example : low memory
+2017-08-01 07:30:02.000  1234  1250 E Example : out of memory
+2017-08-01 07:30:03.000  1234  1251 D Other   : done";
+        let messages = data.lines().map(|line| parse::threadtime(line).unwrap());
+
+        let mut writer = Writer::open_in_memory().unwrap();
+        writer.batch_size(3).write_all(messages).unwrap();
+        let conn = writer.finish().unwrap();
+
+        let count = |sql: &str| -> i64 { conn.query_row(sql, [], |row| row.get(0)).unwrap() };
+        assert_eq!(count("SELECT count(*) FROM messages"), 4);
+        assert_eq!(count("SELECT count(*) FROM tags"), 3);
+        assert_eq!(count("SELECT count(*) FROM processes"), 1);
+        assert_eq!(count("SELECT count(*) FROM messages WHERE level >= 5"), 2);
+        assert_eq!(
+            count("SELECT count(*) FROM messages_fts WHERE messages_fts MATCH 'memory'"),
+            2
+        );
+
+        let (time, tag, process): (String, String, String) = conn
+            .query_row(
+                "SELECT time, tag, process FROM messages_view WHERE level = 6",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .unwrap();
+        assert_eq!(time, "2017-08-01 07:30:02.000000000");
+        assert_eq!(tag, "Example");
+        assert_eq!(process, "com.example");
+
+        let plan: String = conn
+            .query_row(
+                "EXPLAIN QUERY PLAN SELECT * FROM messages WHERE pid = 1234",
+                [],
+                |row| row.get(3),
+            )
+            .unwrap();
+        assert!(plan.contains("messages_pid"), "{}", plan);
+    }
+
+    #[test]
+    fn sqlite_reopen() {
+        let msg = parse::threadtime("08-01 07:30:00.123  1  2 I tag     : content").unwrap();
+
+        let mut writer = Writer::open_in_memory().unwrap();
+        writer.write(&msg).unwrap();
+        let conn = writer.finish().unwrap();
+
+        let mut writer = Writer::new(conn).unwrap();
+        writer.write(&msg).unwrap();
+        let conn = writer.finish().unwrap();
+
+        let tags: i64 = conn
+            .query_row("SELECT count(*) FROM tags", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(tags, 1);
+    }
+
+    #[test]
+    fn sqlite_failed_insert() {
+        let ok = parse::threadtime("08-01 07:30:00.123  1  2 I tag     : content").unwrap();
+        let fail = parse::threadtime("08-01 07:30:00.123  1  2 I tag     : fail").unwrap();
+
+        let conn = Writer::open_in_memory().unwrap().finish().unwrap();
+        conn.execute_batch(
+            "CREATE TRIGGER reject BEFORE INSERT ON messages WHEN NEW.content = 'fail'
+             BEGIN SELECT RAISE(ABORT, 'rejected'); END;",
+        )
+        .unwrap();
+        let mut writer = Writer::new(conn).unwrap();
+        assert!(writer.write(&fail).is_err());
+        writer.write(&ok).unwrap();
+        let conn = writer.finish().unwrap();
+
+        let count: i64 = conn
+            .query_row("SELECT count(*) FROM messages", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(count, 1);
+    }
+
+    #[test]
+    fn sqlite_drop() {
+        let dir = std::env::temp_dir().join(format!("logcat-sqlite-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("logcat.db");
+        let msg = parse::threadtime("08-01 07:30:00.123456  1  2 I tag     : content").unwrap();
+
+        let mut writer = Writer::open(&path).unwrap();
+        writer.write(&msg).unwrap();
+        drop(writer);
+
+        let conn = Connection::open(&path).unwrap();
+        let time: String = conn
+            .query_row("SELECT time FROM messages", [], |row| row.get(0))
+            .unwrap();
+        assert!(time.ends_with("07:30:00.123456000"), "{}", time);
+        drop(conn);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,346 @@
+//! A [`tracing`](https://docs.rs/tracing) layer producing logcat messages.
+//!
+//! Events are converted into messages with the target as tag, such as
+//! `my_crate.net` for `my_crate::net`, and the current process and thread
+//! IDs. The content is the event message followed by the
+//! other event fields, prefixed with the enclosing spans and their fields, as
+//! in `request{id=7}:parse: invalid header len=3`.
+//!
+//! Messages are handed to a [`Sink`], such as a [`WriterSink`], an
+//! [`mpsc::Sender`] or a [`RingBuffer`].
+//!
+//! # Examples
+//!
+//! ```
+//! use logcat::subscriber::{LogcatLayer, RingBuffer};
+//! use tracing_subscriber::layer::SubscriberExt;
+//!
+//! let buffer = RingBuffer::new(100);
+//! let subscriber = tracing_subscriber::registry().with(LogcatLayer::new(buffer.clone()));
+//! tracing::subscriber::with_default(subscriber, || {
+//!     tracing::warn!(len = 3, "invalid header");
+//! });
+//! assert_eq!(buffer.messages()[0].content(), "invalid header len=3");
+//! ```
+
+use crate::format::Formatter;
+use crate::message::{Level, Message, MessageBuilder};
+use crate::sys;
+use chrono::Local;
+use std::collections::VecDeque;
+use std::fmt::{self, Write as _};
+use std::io::Write;
+use std::sync::{mpsc, Arc, Mutex};
+use tracing_core::{
+    field::{Field, Visit},
+    span, Event, Subscriber,
+};
+use tracing_subscriber::{layer::Context, registry::LookupSpan, Layer};
+
+impl From<tracing_core::Level> for Level {
+    fn from(level: tracing_core::Level) -> Level {
+        match level {
+            tracing_core::Level::ERROR => Level::Error,
+            tracing_core::Level::WARN => Level::Warning,
+            tracing_core::Level::INFO => Level::Info,
+            tracing_core::Level::DEBUG => Level::Debug,
+            tracing_core::Level::TRACE => Level::Verbose,
+        }
+    }
+}
+
+/// Receives the messages of a [`LogcatLayer`].
+pub trait Sink: Send + Sync + 'static {
+    /// Handles one message.
+    fn send(&self, message: Message);
+}
+
+/// Sends messages to a channel, dropping them if the receiver is gone.
+impl Sink for mpsc::Sender<Message> {
+    fn send(&self, message: Message) {
+        let _ = mpsc::Sender::send(self, message);
+    }
+}
+
+/// Sends messages to a channel, dropping them if the receiver is gone.
+impl Sink for mpsc::SyncSender<Message> {
+    fn send(&self, message: Message) {
+        let _ = mpsc::SyncSender::send(self, message);
+    }
+}
+
+/// Writes messages with a [`Formatter`], `threadtime` by default.
+pub struct WriterSink<W> {
+    out: Mutex<W>,
+    formatter: Formatter,
+}
+
+impl<W: Write + Send + 'static> WriterSink<W> {
+    /// Creates a new `WriterSink` writing `threadtime` lines to `out`.
+    pub fn new(out: W) -> WriterSink<W> {
+        WriterSink {
+            out: Mutex::new(out),
+            formatter: Formatter::default(),
+        }
+    }
+
+    /// Sets the formatter used to render messages.
+    pub fn formatter(&mut self, value: Formatter) -> &mut Self {
+        self.formatter = value;
+        self
+    }
+}
+
+impl<W: Write + Send + 'static> Sink for WriterSink<W> {
+    fn send(&self, message: Message) {
+        let line = self.formatter.format(&message);
+        if let Ok(mut out) = self.out.lock() {
+            // There is nowhere to report a failure to log.
+            let _ = out.write_all(line.as_bytes());
+        }
+    }
+}
+
+/// Keeps the most recent messages in memory.
+///
+/// Clones share the same messages, so one clone can be given to a
+/// [`LogcatLayer`] and another used to inspect the messages, such as in
+/// tests.
+#[derive(Clone, Debug)]
+pub struct RingBuffer {
+    messages: Arc<Mutex<VecDeque<Message>>>,
+    capacity: usize,
+}
+
+impl RingBuffer {
+    /// Creates a new `RingBuffer` keeping at most `capacity` messages.
+    pub fn new(capacity: usize) -> RingBuffer {
+        RingBuffer {
+            messages: Arc::new(Mutex::new(VecDeque::with_capacity(capacity))),
+            capacity,
+        }
+    }
+
+    /// Returns the kept messages, oldest first.
+    pub fn messages(&self) -> Vec<Message> {
+        self.lock().iter().cloned().collect()
+    }
+
+    /// Removes all kept messages.
+    pub fn clear(&self) {
+        self.lock().clear();
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, VecDeque<Message>> {
+        self.messages.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Sink for RingBuffer {
+    fn send(&self, message: Message) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut messages = self.lock();
+        if messages.len() == self.capacity {
+            messages.pop_front();
+        }
+        messages.push_back(message);
+    }
+}
+
+/// A [`Layer`] converting events into messages for a [`Sink`].
+pub struct LogcatLayer<K> {
+    sink: K,
+}
+
+impl<K: Sink> LogcatLayer<K> {
+    /// Creates a new `LogcatLayer` handing messages to `sink`.
+    pub fn new(sink: K) -> LogcatLayer<K> {
+        LogcatLayer { sink }
+    }
+}
+
+// The formatted fields of a span, stored in its extensions.
+struct SpanFields(String);
+
+#[derive(Default)]
+struct FieldVisitor {
+    message: String,
+    fields: String,
+}
+
+impl FieldVisitor {
+    // Returns the string to append the value of `field` to.
+    fn field(&mut self, field: &Field) -> &mut String {
+        if field.name() == "message" {
+            return &mut self.message;
+        }
+        if !self.fields.is_empty() {
+            self.fields.push(' ');
+        }
+        self.fields.push_str(field.name());
+        self.fields.push('=');
+        &mut self.fields
+    }
+}
+
+impl Visit for FieldVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.field(field).push_str(value);
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        let _ = write!(self.field(field), "{:?}", value);
+    }
+}
+
+impl<S, K> Layer<S> for LogcatLayer<K>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    K: Sink,
+{
+    fn on_new_span(&self, attrs: &span::Attributes<'_>, id: &span::Id, ctx: Context<'_, S>) {
+        if let Some(span) = ctx.span(id) {
+            let mut visitor = FieldVisitor::default();
+            attrs.record(&mut visitor);
+            span.extensions_mut().insert(SpanFields(visitor.fields));
+        }
+    }
+
+    fn on_record(&self, id: &span::Id, values: &span::Record<'_>, ctx: Context<'_, S>) {
+        if let Some(span) = ctx.span(id) {
+            let mut extensions = span.extensions_mut();
+            if let Some(SpanFields(fields)) = extensions.get_mut::<SpanFields>() {
+                let mut visitor = FieldVisitor {
+                    fields: std::mem::take(fields),
+                    ..FieldVisitor::default()
+                };
+                values.record(&mut visitor);
+                *fields = visitor.fields;
+            }
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let mut content = String::new();
+        if let Some(scope) = ctx.event_scope(event) {
+            for span in scope.from_root() {
+                content.push_str(span.name());
+                if let Some(SpanFields(fields)) = span.extensions().get::<SpanFields>() {
+                    if !fields.is_empty() {
+                        let _ = write!(content, "{{{}}}", fields);
+                    }
+                }
+                content.push(':');
+            }
+            content.push(' ');
+        }
+
+        let mut visitor = FieldVisitor::default();
+        event.record(&mut visitor);
+        content.push_str(&visitor.message);
+        if !visitor.message.is_empty() && !visitor.fields.is_empty() {
+            content.push(' ');
+        }
+        content.push_str(&visitor.fields);
+
+        let metadata = event.metadata();
+        let message = MessageBuilder::new()
+            .level((*metadata.level()).into())
+            .tag(&sys::tag(metadata.target()))
+            .content(&content)
+            .date_time(Local::now().naive_local())
+            .process_id(sys::process_id())
+            .thread_id(sys::thread_id())
+            .build()
+            .expect("all required fields are set");
+        self.sink.send(message);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::message::Level;
+    use crate::parse;
+    use crate::subscriber::{LogcatLayer, RingBuffer, Sink, WriterSink};
+    use std::io::Write;
+    use std::sync::{mpsc, Arc, Mutex};
+    use tracing_subscriber::layer::SubscriberExt;
+
+    #[test]
+    fn layer() {
+        let buffer = RingBuffer::new(2);
+        let subscriber = tracing_subscriber::registry().with(LogcatLayer::new(buffer.clone()));
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::trace!(target: "app", "dropped from the ring buffer");
+            let span = tracing::info_span!("request", id = 7, path = "/");
+            let _request = span.enter();
+            tracing::debug_span!("parse", len = tracing::field::Empty).in_scope(|| {
+                tracing::Span::current().record("len", 3);
+                tracing::warn!(target: "app", code = 400, "invalid header");
+            });
+            tracing::error!(done = true);
+        });
+
+        let messages = buffer.messages();
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0].level(), Level::Warning);
+        assert_eq!(messages[0].tag(), "app");
+        assert_eq!(
+            messages[0].content(),
+            "request{id=7 path=/}:parse{len=3}: invalid header code=400"
+        );
+        assert_eq!(messages[0].process_id(), Some(std::process::id() as i32));
+        assert_eq!(messages[1].level(), Level::Error);
+        assert_eq!(messages[1].tag(), "logcat.subscriber.tests");
+        assert_eq!(messages[1].content(), "request{id=7 path=/}: done=true");
+
+        buffer.clear();
+        assert!(buffer.messages().is_empty());
+    }
+
+    #[test]
+    fn sinks() {
+        #[derive(Clone, Default)]
+        struct Shared(Arc<Mutex<Vec<u8>>>);
+
+        impl Write for Shared {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                self.0.lock().unwrap().write(buf)
+            }
+
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let (sender, receiver) = mpsc::channel();
+        let output = Shared::default();
+        let subscriber = tracing_subscriber::registry()
+            .with(LogcatLayer::new(sender))
+            .with(LogcatLayer::new(WriterSink::new(output.clone())));
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info!(target: "app", "started");
+            tracing::info!("with the module path");
+        });
+
+        let msg = receiver.recv().unwrap();
+        assert_eq!(msg.content(), "started");
+
+        let output = String::from_utf8(output.0.lock().unwrap().clone()).unwrap();
+        let parsed: Vec<_> = output
+            .lines()
+            .map(|line| parse::threadtime(line).unwrap())
+            .collect();
+        assert_eq!(parsed[0].tag(), "app");
+        assert_eq!(parsed[0].content(), "started");
+        assert_eq!(parsed[0].thread_id(), msg.thread_id());
+        assert_eq!(parsed[1].tag(), "logcat.subscriber.tests");
+        assert_eq!(parsed[1].content(), "with the module path");
+
+        let buffer = RingBuffer::new(0);
+        buffer.send(msg);
+        assert!(buffer.messages().is_empty());
+    }
+}
@@ -0,0 +1,26 @@
+//! Identifiers of the current process and thread, and tags for host-side
+//! messages.
+
+/// Returns the ID of the current process.
+pub(crate) fn process_id() -> i32 {
+    std::process::id() as i32
+}
+
+/// Returns the kernel ID of the current thread, as logged by Android.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub(crate) fn thread_id() -> i32 {
+    // SAFETY: gettid has no preconditions and cannot fail.
+    unsafe { libc::gettid() }
+}
+
+/// Returns the ID of the current process, as there is no portable thread ID.
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+pub(crate) fn thread_id() -> i32 {
+    process_id()
+}
+
+/// Returns a tag for a `log` or `tracing` target, such as `my_crate.net` for
+/// `my_crate::net`, as a `:` would end the tag of a logcat line.
+pub(crate) fn tag(target: &str) -> String {
+    target.replace("::", ".").replace(':', ".")
+}
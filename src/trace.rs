@@ -0,0 +1,199 @@
+//! Exporting messages as Chrome Trace Event JSON.
+//!
+//! Each message becomes an instant event on the thread that logged it, named
+//! by its tag and with its content and level as arguments. Process names
+//! known from the written messages are added as metadata events, so that
+//! tracks are labelled. The output can be opened in `ui.perfetto.dev` or
+//! `chrome://tracing`, next to systrace and Perfetto captures.
+//!
+//! Event timestamps are the microseconds since the Unix epoch of the message
+//! timestamps, taken as UTC. Use [`TraceWriter::clock_offset`] to line them up
+//! with the clock of a trace.
+//!
+//! # Examples
+//!
+//! ```
+//! use logcat::{parse, trace::TraceWriter};
+//!
+//! let line = "2017-08-01 07:30:00.123  1234  1250 W tag     : content";
+//! let msg = parse::threadtime(line).unwrap();
+//!
+//! let mut writer = TraceWriter::new(Vec::new());
+//! writer.write(&msg).unwrap();
+//! let output = String::from_utf8(writer.finish().unwrap()).unwrap();
+//! assert!(output.starts_with(r#"{"traceEvents":["#));
+//! ```
+
+use crate::analyze::ProcessTracker;
+use crate::message::Message;
+use chrono::Duration;
+use serde_json::json;
+use std::collections::HashSet;
+use std::io::{self, Write};
+
+/// Writes messages as a Chrome Trace Event JSON object.
+///
+/// Messages without a timestamp cannot be placed on the timeline and are
+/// skipped. Call [`finish`](TraceWriter::finish) to complete the JSON object.
+pub struct TraceWriter<W: Write> {
+    inner: W,
+    offset: Duration,
+    started: bool,
+    tracker: ProcessTracker,
+    named: HashSet<(i32, String)>,
+}
+
+impl<W: Write> TraceWriter<W> {
+    /// Creates a new `TraceWriter` writing to `inner`.
+    pub fn new(inner: W) -> TraceWriter<W> {
+        TraceWriter {
+            inner,
+            offset: Duration::zero(),
+            started: false,
+            tracker: ProcessTracker::new(),
+            named: HashSet::new(),
+        }
+    }
+
+    /// Sets the offset added to message timestamps, such as the difference
+    /// between the wall clock of the device and the clock of a trace.
+    pub fn clock_offset(&mut self, value: Duration) -> &mut Self {
+        self.offset = value;
+        self
+    }
+
+    /// Writes one message as an instant event.
+    pub fn write(&mut self, msg: &Message) -> io::Result<()> {
+        self.tracker.track(msg);
+        let Some(date_time) = msg.date_time() else {
+            return Ok(());
+        };
+        let ts = (date_time + self.offset)
+            .and_utc()
+            .timestamp_nanos_opt()
+            .unwrap_or_default() as f64
+            / 1000.0;
+        let pid = msg.process_id().unwrap_or(0);
+        let tid = msg.thread_id().unwrap_or(pid);
+
+        if let Some(name) = self.tracker.process_name_for(msg) {
+            if self.named.insert((pid, name.to_owned())) {
+                let event = json!({
+                    "name": "process_name",
+                    "ph": "M",
+                    "pid": pid,
+                    "args": { "name": name },
+                });
+                self.write_event(&event)?;
+            }
+        }
+
+        let event = json!({
+            "name": msg.tag(),
+            "cat": "logcat",
+            "ph": "i",
+            "s": "t",
+            "ts": ts,
+            "pid": pid,
+            "tid": tid,
+            "args": {
+                "content": msg.content(),
+                "level": msg.level().short(),
+            },
+        });
+        self.write_event(&event)
+    }
+
+    /// Writes all `messages`.
+    pub fn write_all<I: IntoIterator<Item = Message>>(&mut self, messages: I) -> io::Result<()> {
+        for msg in messages {
+            self.write(&msg)?;
+        }
+        Ok(())
+    }
+
+    /// Completes the JSON object and returns the underlying writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        if !self.started {
+            self.inner.write_all(b"{\"traceEvents\":[")?;
+        }
+        self.inner.write_all(b"\n]}\n")?;
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+
+    fn write_event(&mut self, event: &serde_json::Value) -> io::Result<()> {
+        if self.started {
+            self.inner.write_all(b",\n")?;
+        } else {
+            self.inner.write_all(b"{\"traceEvents\":[\n")?;
+            self.started = true;
+        }
+        serde_json::to_writer(&mut self.inner, event)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::message::{Level, MessageBuilder};
+    use crate::parse;
+    use crate::trace::TraceWriter;
+    use chrono::Duration;
+    use serde_json::{json, Value};
+
+    #[test]
+    fn trace() {
+        let data = "\
+2017-08-01 07:30:00.000  1000  1020 I ActivityManager: Start proc 1234:com.example/u0a12 for activity com.example/.Main
+2017-08-01 07:30:00.001  1234  1250 W Example : \"quoted\"";
+        let mut messages: Vec<_> = data
+            .lines()
+            .map(|line| parse::threadtime(line).unwrap())
+            .collect();
+        messages.push(
+            MessageBuilder::new()
+                .level(Level::Info)
+                .tag("tag")
+                .content("no time")
+                .build()
+                .unwrap(),
+        );
+
+        let mut writer = TraceWriter::new(Vec::new());
+        writer
+            .clock_offset(Duration::milliseconds(-1))
+            .write_all(messages)
+            .unwrap();
+        let output = writer.finish().unwrap();
+
+        let trace: Value = serde_json::from_slice(&output).unwrap();
+        let events = trace["traceEvents"].as_array().unwrap();
+        assert_eq!(events.len(), 3);
+        assert_eq!(events[0]["ph"], "i");
+        assert_eq!(events[0]["ts"], 1_501_572_599_999_000.0);
+        assert_eq!(
+            events[1],
+            json!({
+                "name": "process_name",
+                "ph": "M",
+                "pid": 1234,
+                "args": { "name": "com.example" },
+            })
+        );
+        assert_eq!(events[2]["name"], "Example");
+        assert_eq!(events[2]["ts"], 1_501_572_600_000_000.0);
+        assert_eq!(events[2]["tid"], 1250);
+        assert_eq!(
+            events[2]["args"],
+            json!({ "content": "\"quoted\"", "level": "W" })
+        );
+    }
+
+    #[test]
+    fn trace_empty() {
+        let output = TraceWriter::new(Vec::new()).finish().unwrap();
+        let trace: Value = serde_json::from_slice(&output).unwrap();
+        assert_eq!(trace, json!({ "traceEvents": [] }));
+    }
+}